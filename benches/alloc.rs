@@ -0,0 +1,94 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use obcore::{EvalBuffer, Op, OrderBook};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+use common::{BACKENDS, BenchOrder, NewBook};
+
+/// System allocator that counts allocations
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// A book with one order resting outside the traded price on each side, so
+/// levels come and go at 100 without emptying either side
+fn setup(new_book: NewBook) -> (OrderBook<BenchOrder>, EvalBuffer<BenchOrder>) {
+    let mut ob = new_book();
+    let mut buffer = EvalBuffer::new();
+    ob.eval_into(
+        [
+            Op::Insert(BenchOrder::new(0, true, 90, 100)),
+            Op::Insert(BenchOrder::new(1, false, 110, 100)),
+        ],
+        &mut buffer,
+    );
+    ob.apply_buffer(&mut buffer).unwrap();
+    (ob, buffer)
+}
+
+/// Rests a sell, partially fills it with a buy and cancels the rest
+fn step(ob: &mut OrderBook<BenchOrder>, buffer: &mut EvalBuffer<BenchOrder>, id: &mut u64) {
+    *id += 2;
+    ob.eval_into([Op::Insert(BenchOrder::new(*id, false, 100, 10))], buffer);
+    ob.apply_buffer(buffer).unwrap();
+    ob.eval_into(
+        [
+            Op::Insert(BenchOrder::new(*id + 1, true, 100, 4)),
+            Op::Delete(*id),
+        ],
+        buffer,
+    );
+    black_box(buffer.matches().count());
+    ob.apply_buffer(buffer).unwrap();
+}
+
+/// Benchmark eval_into and apply_buffer in steady state, after checking that
+/// they do not allocate. Holds because BenchOrder ids are u64: ids that
+/// allocate when cloned, such as String, allocate per instruction and match
+fn bench_eval_into(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval_into");
+    group.throughput(Throughput::Elements(1));
+
+    for (suffix, new_book) in BACKENDS {
+        let (mut ob, mut buffer) = setup(new_book);
+        let mut id = 1;
+        for _ in 0..1_000 {
+            step(&mut ob, &mut buffer, &mut id);
+        }
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..10_000 {
+            step(&mut ob, &mut buffer, &mut id);
+        }
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        assert_eq!(allocations, 0, "steady state matching allocated");
+
+        group.bench_function(format!("steady_state{suffix}"), |b| {
+            b.iter(|| step(&mut ob, &mut buffer, &mut id));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_eval_into);
+criterion_main!(benches);
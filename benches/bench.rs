@@ -1,75 +1,26 @@
 use criterion::{BatchSize, Criterion, Throughput, black_box, criterion_group, criterion_main};
-use obcore::OrderBook;
-use obcore::OrderInterface;
-
-/// Order type for benchmarks
-#[derive(Default, Debug, PartialEq, Eq, Clone)]
-pub struct BenchOrder {
-    id: u64,
-    is_buy: bool,
-    price: u64,
-    quantity: u64,
-    remaining: u64,
-}
-
-impl BenchOrder {
-    pub fn new(id: u64, is_buy: bool, price: u64, quantity: u64) -> Self {
-        Self {
-            id,
-            is_buy,
-            price,
-            quantity,
-            remaining: quantity,
-        }
-    }
-}
-
-impl OrderInterface for BenchOrder {
-    type T = u64;
-    type N = u64;
+use obcore::{Op, OrderBook};
 
-    fn id(&self) -> &u64 {
-        &self.id
-    }
-
-    fn price(&self) -> u64 {
-        self.price
-    }
-
-    fn is_buy(&self) -> bool {
-        self.is_buy
-    }
-
-    fn quantity(&self) -> u64 {
-        self.quantity
-    }
-
-    fn remaining(&self) -> u64 {
-        self.remaining
-    }
-
-    fn fill(&mut self, quantity: u64) {
-        self.remaining -= quantity;
-    }
-}
+mod common;
+use common::{BACKENDS, BenchOrder, NewBook};
 
 /// Helper to pre-populate an order book with N orders on each side
 fn populate_book(ob: &mut OrderBook<BenchOrder>, count: usize) -> u64 {
     for i in 0..count {
         let buy = BenchOrder::new(i as u64, true, 900 + (i % 50) as u64, 100);
         let (_, instructions) = ob.eval_insert(buy);
-        ob.apply(instructions);
+        ob.apply(instructions).unwrap();
 
         let sell = BenchOrder::new((i + count) as u64, false, 1100 + (i % 50) as u64, 100);
         let (_, instructions) = ob.eval_insert(sell);
-        ob.apply(instructions);
+        ob.apply(instructions).unwrap();
     }
     (count * 2) as u64
 }
 
 /// Create a populated order book with given depth
-fn make_book(depth: usize) -> (OrderBook<BenchOrder>, u64) {
-    let mut ob = OrderBook::<BenchOrder>::default();
+fn make_book(new_book: NewBook, depth: usize) -> (OrderBook<BenchOrder>, u64) {
+    let mut ob = new_book();
     let next_id = populate_book(&mut ob, depth / 2);
     (ob, next_id)
 }
@@ -79,41 +30,47 @@ fn bench_eval_insert(c: &mut Criterion) {
     let mut group = c.benchmark_group("eval_insert");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("empty", |b| {
-        b.iter_batched_ref(
-            || {
-                let ob = OrderBook::<BenchOrder>::default();
-                let order = BenchOrder::new(0, true, 900, 100);
-                (ob, Some(order))
-            },
-            |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("empty{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let ob = new_book();
+                    let order = BenchOrder::new(0, true, 900, 100);
+                    (ob, Some(order))
+                },
+                |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_100", |b| {
-        b.iter_batched_ref(
-            || {
-                let (ob, next_id) = make_book(100);
-                let order = BenchOrder::new(next_id, true, 895, 100);
-                (ob, Some(order))
-            },
-            |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_100{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (ob, next_id) = make_book(new_book, 100);
+                    let order = BenchOrder::new(next_id, true, 895, 100);
+                    (ob, Some(order))
+                },
+                |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_1000", |b| {
-        b.iter_batched_ref(
-            || {
-                let (ob, next_id) = make_book(1000);
-                let order = BenchOrder::new(next_id, true, 895, 100);
-                (ob, Some(order))
-            },
-            |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_1000{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (ob, next_id) = make_book(new_book, 1000);
+                    let order = BenchOrder::new(next_id, true, 895, 100);
+                    (ob, Some(order))
+                },
+                |(ob, order)| black_box(ob.eval_insert(black_box(order.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
     group.finish();
 }
@@ -123,10 +80,25 @@ fn bench_apply_insert(c: &mut Criterion) {
     let mut group = c.benchmark_group("apply_insert");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("empty", |b| {
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("empty{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    let order = BenchOrder::new(0, true, 900, 100);
+                    let (_, instructions) = ob.eval_insert(order);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.bench_function("empty_reserved", |b| {
         b.iter_batched_ref(
             || {
-                let mut ob = OrderBook::<BenchOrder>::default();
+                let mut ob = OrderBook::<BenchOrder>::with_capacity(1);
                 let order = BenchOrder::new(0, true, 900, 100);
                 let (_, instructions) = ob.eval_insert(order);
                 (ob, Some(instructions))
@@ -136,31 +108,35 @@ fn bench_apply_insert(c: &mut Criterion) {
         );
     });
 
-    group.bench_function("depth_100", |b| {
-        b.iter_batched_ref(
-            || {
-                let (mut ob, next_id) = make_book(100);
-                let order = BenchOrder::new(next_id, true, 895, 100);
-                let (_, instructions) = ob.eval_insert(order);
-                (ob, Some(instructions))
-            },
-            |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_100{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (mut ob, next_id) = make_book(new_book, 100);
+                    let order = BenchOrder::new(next_id, true, 895, 100);
+                    let (_, instructions) = ob.eval_insert(order);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_1000", |b| {
-        b.iter_batched_ref(
-            || {
-                let (mut ob, next_id) = make_book(1000);
-                let order = BenchOrder::new(next_id, true, 895, 100);
-                let (_, instructions) = ob.eval_insert(order);
-                (ob, Some(instructions))
-            },
-            |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_1000{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (mut ob, next_id) = make_book(new_book, 1000);
+                    let order = BenchOrder::new(next_id, true, 895, 100);
+                    let (_, instructions) = ob.eval_insert(order);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
     group.finish();
 }
@@ -170,41 +146,47 @@ fn bench_eval_cancel(c: &mut Criterion) {
     let mut group = c.benchmark_group("eval_cancel");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("single", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                let order = BenchOrder::new(0, true, 900, 100);
-                let (_, instructions) = ob.eval_insert(order);
-                ob.apply(instructions);
-                (ob, 0u64)
-            },
-            |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("single{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    let order = BenchOrder::new(0, true, 900, 100);
+                    let (_, instructions) = ob.eval_insert(order);
+                    ob.apply(instructions).unwrap();
+                    (ob, 0u64)
+                },
+                |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_100", |b| {
-        b.iter_batched_ref(
-            || {
-                let (ob, _) = make_book(100);
-                (ob, 50u64) // Cancel middle order
-            },
-            |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_100{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (ob, _) = make_book(new_book, 100);
+                    (ob, 50u64) // Cancel middle order
+                },
+                |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_1000", |b| {
-        b.iter_batched_ref(
-            || {
-                let (ob, _) = make_book(1000);
-                (ob, 500u64) // Cancel middle order
-            },
-            |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_1000{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (ob, _) = make_book(new_book, 1000);
+                    (ob, 500u64) // Cancel middle order
+                },
+                |(ob, order_id)| black_box(ob.eval_cancel(black_box(*order_id))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
     group.finish();
 }
@@ -214,44 +196,50 @@ fn bench_apply_cancel(c: &mut Criterion) {
     let mut group = c.benchmark_group("apply_cancel");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("single", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                let order = BenchOrder::new(0, true, 900, 100);
-                let (_, instructions) = ob.eval_insert(order);
-                ob.apply(instructions);
-                let cancel_instr = ob.eval_cancel(0u64);
-                (ob, Some(cancel_instr))
-            },
-            |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("single{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    let order = BenchOrder::new(0, true, 900, 100);
+                    let (_, instructions) = ob.eval_insert(order);
+                    ob.apply(instructions).unwrap();
+                    let cancel_instr = ob.eval_cancel(0u64);
+                    (ob, Some(cancel_instr))
+                },
+                |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_100", |b| {
-        b.iter_batched_ref(
-            || {
-                let (mut ob, _) = make_book(100);
-                let cancel_instr = ob.eval_cancel(50u64);
-                (ob, Some(cancel_instr))
-            },
-            |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_100{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (mut ob, _) = make_book(new_book, 100);
+                    let cancel_instr = ob.eval_cancel(50u64);
+                    (ob, Some(cancel_instr))
+                },
+                |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("depth_1000", |b| {
-        b.iter_batched_ref(
-            || {
-                let (mut ob, _) = make_book(1000);
-                let cancel_instr = ob.eval_cancel(500u64);
-                (ob, Some(cancel_instr))
-            },
-            |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("depth_1000{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let (mut ob, _) = make_book(new_book, 1000);
+                    let cancel_instr = ob.eval_cancel(500u64);
+                    (ob, Some(cancel_instr))
+                },
+                |(ob, cancel_instr)| ob.apply(black_box(vec![cancel_instr.take().unwrap()])),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
     group.finish();
 }
@@ -261,54 +249,60 @@ fn bench_eval_match(c: &mut Criterion) {
     let mut group = c.benchmark_group("eval_match");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("1_level", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                let sell = BenchOrder::new(0, false, 1000, 100);
-                let (_, instructions) = ob.eval_insert(sell);
-                ob.apply(instructions);
-                let buy = BenchOrder::new(1, true, 1000, 100);
-                (ob, Some(buy))
-            },
-            |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
-
-    group.bench_function("5_levels", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                for i in 0..5u64 {
-                    let sell = BenchOrder::new(i, false, 1000 + i, 10);
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("1_level{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    let sell = BenchOrder::new(0, false, 1000, 100);
                     let (_, instructions) = ob.eval_insert(sell);
-                    ob.apply(instructions);
-                }
-                let buy = BenchOrder::new(5, true, 1005, 50);
-                (ob, Some(buy))
-            },
-            |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
+                    ob.apply(instructions).unwrap();
+                    let buy = BenchOrder::new(1, true, 1000, 100);
+                    (ob, Some(buy))
+                },
+                |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("10_levels", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                for i in 0..10u64 {
-                    let sell = BenchOrder::new(i, false, 1000 + i, 10);
-                    let (_, instructions) = ob.eval_insert(sell);
-                    ob.apply(instructions);
-                }
-                let buy = BenchOrder::new(10, true, 1010, 100);
-                (ob, Some(buy))
-            },
-            |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("5_levels{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    for i in 0..5u64 {
+                        let sell = BenchOrder::new(i, false, 1000 + i, 10);
+                        let (_, instructions) = ob.eval_insert(sell);
+                        ob.apply(instructions).unwrap();
+                    }
+                    let buy = BenchOrder::new(5, true, 1005, 50);
+                    (ob, Some(buy))
+                },
+                |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("10_levels{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    for i in 0..10u64 {
+                        let sell = BenchOrder::new(i, false, 1000 + i, 10);
+                        let (_, instructions) = ob.eval_insert(sell);
+                        ob.apply(instructions).unwrap();
+                    }
+                    let buy = BenchOrder::new(10, true, 1010, 100);
+                    (ob, Some(buy))
+                },
+                |(ob, buy)| black_box(ob.eval_insert(black_box(buy.take().unwrap()))),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
     group.finish();
 }
@@ -318,60 +312,94 @@ fn bench_apply_match(c: &mut Criterion) {
     let mut group = c.benchmark_group("apply_match");
     group.throughput(Throughput::Elements(1));
 
-    group.bench_function("1_level", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                let sell = BenchOrder::new(0, false, 1000, 100);
-                let (_, instructions) = ob.eval_insert(sell);
-                ob.apply(instructions);
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("1_level{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    let sell = BenchOrder::new(0, false, 1000, 100);
+                    let (_, instructions) = ob.eval_insert(sell);
+                    ob.apply(instructions).unwrap();
+
+                    let buy = BenchOrder::new(1, true, 1000, 100);
+                    let (_, instructions) = ob.eval_insert(buy);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-                let buy = BenchOrder::new(1, true, 1000, 100);
-                let (_, instructions) = ob.eval_insert(buy);
-                (ob, Some(instructions))
-            },
-            |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("5_levels{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    for i in 0..5u64 {
+                        let sell = BenchOrder::new(i, false, 1000 + i, 10);
+                        let (_, instructions) = ob.eval_insert(sell);
+                        ob.apply(instructions).unwrap();
+                    }
+
+                    let buy = BenchOrder::new(5, true, 1005, 50);
+                    let (_, instructions) = ob.eval_insert(buy);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-    group.bench_function("5_levels", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                for i in 0..5u64 {
-                    let sell = BenchOrder::new(i, false, 1000 + i, 10);
-                    let (_, instructions) = ob.eval_insert(sell);
-                    ob.apply(instructions);
-                }
+    for (suffix, new_book) in BACKENDS {
+        group.bench_function(format!("10_levels{suffix}"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut ob = new_book();
+                    for i in 0..10u64 {
+                        let sell = BenchOrder::new(i, false, 1000 + i, 10);
+                        let (_, instructions) = ob.eval_insert(sell);
+                        ob.apply(instructions).unwrap();
+                    }
+
+                    let buy = BenchOrder::new(10, true, 1010, 100);
+                    let (_, instructions) = ob.eval_insert(buy);
+                    (ob, Some(instructions))
+                },
+                |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
+                BatchSize::LargeInput,
+            );
+        });
+    }
 
-                let buy = BenchOrder::new(5, true, 1005, 50);
-                let (_, instructions) = ob.eval_insert(buy);
-                (ob, Some(instructions))
-            },
-            |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
-            BatchSize::LargeInput,
-        );
-    });
+    group.finish();
+}
 
-    group.bench_function("10_levels", |b| {
-        b.iter_batched_ref(
-            || {
-                let mut ob = OrderBook::<BenchOrder>::default();
-                for i in 0..10u64 {
-                    let sell = BenchOrder::new(i, false, 1000 + i, 10);
-                    let (_, instructions) = ob.eval_insert(sell);
-                    ob.apply(instructions);
-                }
+/// Benchmark fused eval and apply matching across varying number of price levels
+fn bench_process_match(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process_match");
+    group.throughput(Throughput::Elements(1));
 
-                let buy = BenchOrder::new(10, true, 1010, 100);
-                let (_, instructions) = ob.eval_insert(buy);
-                (ob, Some(instructions))
-            },
-            |(ob, instructions)| ob.apply(black_box(instructions.take().unwrap())),
-            BatchSize::LargeInput,
-        );
-    });
+    for (suffix, new_book) in BACKENDS {
+        for levels in [1u64, 5, 10] {
+            group.bench_function(format!("{levels}_levels{suffix}"), |b| {
+                b.iter_batched_ref(
+                    || {
+                        let mut ob = new_book();
+                        for i in 0..levels {
+                            let sell = BenchOrder::new(i, false, 1000 + i, 100 / levels);
+                            ob.process(Op::Insert(sell)).unwrap();
+                        }
+                        let buy = BenchOrder::new(levels, true, 1000 + levels, 100);
+                        (ob, Some(buy))
+                    },
+                    |(ob, buy)| black_box(ob.process(Op::Insert(black_box(buy.take().unwrap())))),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
 
     group.finish();
 }
@@ -383,6 +411,7 @@ criterion_group!(
     bench_eval_cancel,
     bench_apply_cancel,
     bench_eval_match,
-    bench_apply_match
+    bench_apply_match,
+    bench_process_match
 );
 criterion_main!(benches);
@@ -0,0 +1,63 @@
+use obcore::{OrderBook, OrderInterface};
+
+/// Order type for benchmarks
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct BenchOrder {
+    id: u64,
+    is_buy: bool,
+    price: u64,
+    quantity: u64,
+    remaining: u64,
+}
+
+impl BenchOrder {
+    pub fn new(id: u64, is_buy: bool, price: u64, quantity: u64) -> Self {
+        Self {
+            id,
+            is_buy,
+            price,
+            quantity,
+            remaining: quantity,
+        }
+    }
+}
+
+impl OrderInterface for BenchOrder {
+    type T = u64;
+    type N = u64;
+
+    fn id(&self) -> &u64 {
+        &self.id
+    }
+
+    fn price(&self) -> u64 {
+        self.price
+    }
+
+    fn is_buy(&self) -> bool {
+        self.is_buy
+    }
+
+    fn quantity(&self) -> u64 {
+        self.quantity
+    }
+
+    fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    fn fill(&mut self, quantity: u64) {
+        self.remaining -= quantity;
+    }
+}
+
+/// Constructor for an empty book
+pub type NewBook = fn() -> OrderBook<BenchOrder>;
+
+/// Book constructors to compare, with the suffix for their benchmark names
+pub const BACKENDS: [(&str, NewBook); 2] = [("", OrderBook::default), ("_ladder", ladder_book)];
+
+/// A book with a tick ladder covering every benchmark price
+fn ladder_book() -> OrderBook<BenchOrder> {
+    OrderBook::with_ladder(0, 2047, 1)
+}
@@ -0,0 +1,174 @@
+use crate::{level::LevelRef, ob::OrderBook, order::OrderInterface};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Analytics
+//
+// Everything is computed in `O::N`. Every division truncates towards zero, so
+// for unsigned integer types results round down. Ratios are returned scaled by
+// a caller-chosen `scale` (e.g. 10_000 for basis points).
+// ─────────────────────────────────────────────────────────────────────────────
+
+impl<O: OrderInterface> OrderBook<O> {
+    /// Returns best ask minus best bid, if both sides are present. None if the
+    /// book is locked or crossed, where the difference is not positive.
+    #[inline]
+    pub fn spread(&self) -> Option<O::N> {
+        let (bid, ask) = self.quote()?;
+        Some(ask - bid)
+    }
+
+    /// Returns the midpoint of the best bid and ask, rounded down. None if the
+    /// book is locked or crossed.
+    #[inline]
+    pub fn mid(&self) -> Option<O::N>
+    where
+        O::N: From<u8>,
+    {
+        let (bid, ask) = self.quote()?;
+        Some(bid + (ask - bid) / O::N::from(2))
+    }
+
+    /// Returns the size-weighted mid (microprice), rounded down:
+    /// `(bid_price * ask_qty + ask_price * bid_qty) / (bid_qty + ask_qty)`.
+    /// Leans towards the side with less quantity, where the next trade is likelier.
+    #[inline]
+    pub fn microprice(&self) -> Option<O::N> {
+        let (bid_price, bid_qty) = self.best_bid()?;
+        let (ask_price, ask_qty) = self.best_ask()?;
+        Some((bid_price * ask_qty + ask_price * bid_qty) / (bid_qty + ask_qty))
+    }
+
+    /// Returns the bid share of quantity over the top `n` levels of each side,
+    /// as `scale * bids / (bids + asks)`. Half of `scale` means balanced.
+    /// None if both sides are empty.
+    #[inline]
+    pub fn imbalance(&self, n: usize, scale: O::N) -> Option<O::N> {
+        let bids = weighted_sum(self.bids().take(n), |_| None);
+        let asks = weighted_sum(self.asks().take(n), |_| None);
+        share::<O>(bids, asks, scale)
+    }
+
+    /// Like `imbalance`, but the quantity at the i-th best level of each side is
+    /// multiplied by `weights[i]`. Covers `weights.len()` levels.
+    #[inline]
+    pub fn weighted_imbalance(&self, weights: &[O::N], scale: O::N) -> Option<O::N> {
+        let n = weights.len();
+        let bids = weighted_sum(self.bids().take(n), |i| Some(weights[i]));
+        let asks = weighted_sum(self.asks().take(n), |i| Some(weights[i]));
+        share::<O>(bids, asks, scale)
+    }
+
+    /// Best bid and ask prices, if both are present and the ask is above the bid.
+    #[inline(always)]
+    fn quote(&self) -> Option<(O::N, O::N)> {
+        let (bid, ask) = (self.best_bid()?.0, self.best_ask()?.0);
+        (ask > bid).then_some((bid, ask))
+    }
+}
+
+/// Sums level quantities, multiplying the i-th by `weight(i)` when it returns Some.
+#[inline(always)]
+fn weighted_sum<'a, O: OrderInterface + 'a>(
+    levels: impl Iterator<Item = LevelRef<'a, O>>,
+    weight: impl Fn(usize) -> Option<O::N>,
+) -> O::N {
+    levels
+        .enumerate()
+        .fold(O::N::default(), |acc, (i, level)| match weight(i) {
+            Some(w) => acc + level.total_quantity() * w,
+            None => acc + level.total_quantity(),
+        })
+}
+
+#[inline(always)]
+fn share<O: OrderInterface>(bids: O::N, asks: O::N, scale: O::N) -> Option<O::N> {
+    let total = bids + asks;
+    (total != O::N::default()).then(|| scale * bids / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Instruction, OrderBook, order::TestOrder};
+
+    fn book(orders: &[(&str, bool, u64, u64)]) -> OrderBook<TestOrder> {
+        let mut ob = OrderBook::default();
+        ob.apply(
+            orders
+                .iter()
+                .map(|&(id, is_buy, price, qty)| {
+                    Instruction::Insert(TestOrder::new(id, is_buy, price, qty), qty)
+                })
+                .collect(),
+        )
+        .unwrap();
+        ob
+    }
+
+    #[test]
+    fn test_empty() {
+        let ob = book(&[("b1", true, 100, 10)]);
+        assert!(ob.spread().is_none());
+        assert!(ob.mid().is_none());
+        assert!(ob.microprice().is_none());
+        assert!(
+            OrderBook::<TestOrder>::default()
+                .imbalance(5, 100)
+                .is_none()
+        );
+        assert_eq!(ob.imbalance(5, 100), Some(100));
+    }
+
+    #[test]
+    fn test_spread_and_mid() {
+        let ob = book(&[("b1", true, 100, 10), ("s1", false, 105, 10)]);
+        assert_eq!(ob.spread(), Some(5));
+        assert_eq!(ob.mid(), Some(102)); // 102.5 rounds down
+    }
+
+    #[test]
+    fn test_locked_and_crossed() {
+        for ask in [100, 95] {
+            let ob = book(&[("b1", true, 100, 10), ("s1", false, ask, 10)]);
+            assert!(ob.spread().is_none());
+            assert!(ob.mid().is_none());
+        }
+    }
+
+    #[test]
+    fn test_microprice() {
+        let ob = book(&[("b1", true, 100, 30), ("s1", false, 110, 10)]);
+        // (100 * 10 + 110 * 30) / 40 = 107.5, leaning towards the thin ask
+        assert_eq!(ob.microprice(), Some(107));
+
+        let ob = book(&[("b1", true, 100, 10), ("s1", false, 110, 10)]);
+        assert_eq!(ob.microprice(), Some(105));
+    }
+
+    #[test]
+    fn test_imbalance() {
+        let ob = book(&[
+            ("b1", true, 100, 30),
+            ("b2", true, 99, 30),
+            ("s1", false, 101, 10),
+            ("s2", false, 102, 50),
+        ]);
+        assert_eq!(ob.imbalance(1, 10_000), Some(7_500));
+        assert_eq!(ob.imbalance(2, 10_000), Some(5_000));
+        assert_eq!(ob.imbalance(100, 10_000), Some(5_000));
+        assert_eq!(ob.imbalance(0, 10_000), None);
+    }
+
+    #[test]
+    fn test_weighted_imbalance() {
+        let ob = book(&[
+            ("b1", true, 100, 30),
+            ("b2", true, 99, 30),
+            ("s1", false, 101, 10),
+            ("s2", false, 102, 50),
+        ]);
+        // bids: 30 * 2 + 30 * 1 = 90, asks: 10 * 2 + 50 * 1 = 70
+        assert_eq!(ob.weighted_imbalance(&[2, 1], 1_000), Some(562));
+        assert_eq!(ob.weighted_imbalance(&[1], 1_000), ob.imbalance(1, 1_000));
+        assert_eq!(ob.weighted_imbalance(&[], 1_000), None);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{feed::Sink, level::Level, order::OrderInterface};
+
+/// Source of timestamps for bar aggregation. Units are up to the caller.
+pub trait Clock {
+    fn now(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    #[inline]
+    fn now(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// When a bar closes.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BarKind<O: OrderInterface> {
+    /// Fixed time buckets of this length, aligned to multiples of it.
+    Time(u64),
+    /// Every this much traded quantity. A fill spanning a boundary is split.
+    Volume(O::N),
+    /// Every this many trades.
+    Ticks(u64),
+}
+
+impl<O: OrderInterface> Clone for BarKind<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for BarKind<O> {}
+
+/// An OHLCV bar.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bar<O: OrderInterface> {
+    /// Bucket start for time bars, otherwise the time of the first trade.
+    pub start: u64,
+    /// Time of the last trade.
+    pub end: u64,
+    pub open: O::N,
+    pub high: O::N,
+    pub low: O::N,
+    pub close: O::N,
+    pub volume: O::N,
+    pub trades: u64,
+}
+
+impl<O: OrderInterface> Clone for Bar<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for Bar<O> {}
+
+impl<O: OrderInterface> Bar<O> {
+    #[inline]
+    fn new(start: u64, now: u64, price: O::N) -> Self {
+        Self {
+            start,
+            end: now,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: O::N::default(),
+            trades: 0,
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, now: u64, price: O::N, quantity: O::N) {
+        self.end = now;
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += quantity;
+    }
+}
+
+/// Builds bars from trades. Feed it with `OrderBook::apply_with_bars` or `update`.
+pub struct BarAggregator<O: OrderInterface, C: Clock> {
+    kind: BarKind<O>,
+    clock: C,
+    current: Option<Bar<O>>,
+    completed: Vec<Bar<O>>,
+}
+
+impl<O: OrderInterface, C: Clock> BarAggregator<O, C> {
+    /// Panics if the interval, volume or tick count of `kind` is zero.
+    #[inline]
+    pub fn new(kind: BarKind<O>, clock: C) -> Self {
+        let empty = match kind {
+            BarKind::Time(n) | BarKind::Ticks(n) => n == 0,
+            BarKind::Volume(size) => size == O::N::default(),
+        };
+        assert!(!empty, "bar size must be positive");
+        Self {
+            kind,
+            clock,
+            current: None,
+            completed: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> BarKind<O> {
+        self.kind
+    }
+
+    /// Returns the bar still being built, if any.
+    #[inline]
+    pub fn current(&self) -> Option<&Bar<O>> {
+        self.current.as_ref()
+    }
+
+    /// Returns bars closed since the last `drain`, oldest first.
+    #[inline]
+    pub fn completed(&self) -> &[Bar<O>] {
+        &self.completed
+    }
+
+    /// Removes and returns the closed bars, oldest first.
+    #[inline]
+    pub fn drain(&mut self) -> std::vec::Drain<'_, Bar<O>> {
+        self.completed.drain(..)
+    }
+
+    /// Closes the current time bar if the clock has moved past its bucket.
+    /// Call periodically so quiet periods do not hold a bar open.
+    #[inline]
+    pub fn poll(&mut self) {
+        if let BarKind::Time(interval) = self.kind {
+            let now = self.clock.now();
+            if let Some(bar) = self.current
+                && now >= bar.start + interval
+            {
+                self.close();
+            }
+        }
+    }
+
+    /// Records a trade of `quantity` at `price`, timestamped by the clock.
+    #[inline]
+    pub fn update(&mut self, price: O::N, quantity: O::N) {
+        let now = self.clock.now();
+        match self.kind {
+            BarKind::Time(interval) => {
+                let start = now - now % interval;
+                if self.current.is_some_and(|bar| bar.start != start) {
+                    self.close();
+                }
+                self.trade(start, now, price, quantity);
+            }
+            BarKind::Volume(size) => {
+                let mut left = quantity;
+                while left > O::N::default() {
+                    let filled = self.current.map_or(O::N::default(), |bar| bar.volume);
+                    let take = left.min(size - filled);
+                    self.trade(now, now, price, take);
+                    left -= take;
+                    if filled + take == size {
+                        self.close();
+                    }
+                }
+            }
+            BarKind::Ticks(count) => {
+                self.trade(now, now, price, quantity);
+                if self.current.is_some_and(|bar| bar.trades == count) {
+                    self.close();
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn trade(&mut self, start: u64, now: u64, price: O::N, quantity: O::N) {
+        let bar = self
+            .current
+            .get_or_insert_with(|| Bar::new(start, now, price));
+        bar.add(now, price, quantity);
+        bar.trades += 1;
+    }
+
+    #[inline(always)]
+    fn close(&mut self) {
+        if let Some(bar) = self.current.take() {
+            self.completed.push(bar);
+        }
+    }
+}
+
+impl<O: OrderInterface, C: Clock> Sink<O> for BarAggregator<O, C> {
+    #[inline(always)]
+    fn insert(&mut self, _: &O, _: &Level<O>) {}
+
+    #[inline]
+    fn fill(&mut self, order: &O, quantity: O::N, _: Option<&Level<O>>) {
+        self.update(order.price(), quantity);
+    }
+
+    #[inline(always)]
+    fn reduce(&mut self, _: &O, _: O::N, _: Option<&Level<O>>) {}
+
+    #[inline(always)]
+    fn delete(&mut self, _: &O, _: Option<&Level<O>>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::TestOrder;
+    use std::cell::Cell;
+
+    fn ohlcv(bar: &Bar<TestOrder>) -> (u64, u64, u64, u64, u64, u64) {
+        (
+            bar.open, bar.high, bar.low, bar.close, bar.volume, bar.trades,
+        )
+    }
+
+    #[test]
+    fn test_time_bars() {
+        let time = Cell::new(0);
+        let mut agg = BarAggregator::<TestOrder, _>::new(BarKind::Time(60), || time.get());
+
+        time.set(5);
+        agg.update(100, 10);
+        time.set(30);
+        agg.update(105, 5);
+        time.set(59);
+        agg.update(98, 1);
+        assert!(agg.completed().is_empty());
+
+        time.set(125);
+        agg.update(101, 7);
+        assert_eq!(agg.completed().len(), 1);
+        let bar = agg.completed()[0];
+        assert_eq!((bar.start, bar.end), (0, 59));
+        assert_eq!(ohlcv(&bar), (100, 105, 98, 98, 16, 3));
+        assert_eq!(agg.current().unwrap().start, 120);
+
+        time.set(179);
+        agg.poll();
+        assert_eq!(agg.completed().len(), 1);
+        time.set(180);
+        agg.poll();
+        assert_eq!(agg.drain().count(), 2);
+        assert!(agg.current().is_none());
+    }
+
+    #[test]
+    fn test_volume_bars() {
+        let mut agg = BarAggregator::<TestOrder, _>::new(BarKind::Volume(10), || 0);
+        agg.update(100, 4);
+        agg.update(101, 15);
+        agg.update(99, 1);
+
+        let bars: Vec<_> = agg.completed().iter().map(ohlcv).collect();
+        assert_eq!(
+            bars,
+            vec![(100, 101, 100, 101, 10, 2), (101, 101, 99, 99, 10, 2)]
+        );
+        assert!(agg.current().is_none());
+    }
+
+    #[test]
+    fn test_tick_bars() {
+        let mut agg = BarAggregator::<TestOrder, _>::new(BarKind::Ticks(2), || 0);
+        agg.update(100, 4);
+        agg.update(102, 1);
+        agg.update(101, 3);
+
+        assert_eq!(agg.completed().len(), 1);
+        assert_eq!(ohlcv(&agg.completed()[0]), (100, 102, 100, 102, 5, 2));
+        assert_eq!(agg.current().unwrap().trades, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "bar size must be positive")]
+    fn test_zero_time_bars() {
+        BarAggregator::<TestOrder, _>::new(BarKind::Time(0), || 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "bar size must be positive")]
+    fn test_zero_volume_bars() {
+        BarAggregator::<TestOrder, _>::new(BarKind::Volume(0), || 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "bar size must be positive")]
+    fn test_zero_tick_bars() {
+        BarAggregator::<TestOrder, _>::new(BarKind::Ticks(0), || 0);
+    }
+}
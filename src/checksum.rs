@@ -0,0 +1,145 @@
+use crate::order::OrderInterface;
+use std::fmt::{self, Write};
+
+/// CRC-32 (IEEE 802.3, as used by zlib, OKX and Kraken) lookup table.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Streaming CRC-32. Implements `fmt::Write` so values can be checksummed
+/// through their `Display` impl without allocating.
+#[derive(Clone, Copy)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    #[inline]
+    pub fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    #[inline]
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.crc = CRC32_TABLE[((self.crc ^ b as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    #[inline]
+    pub fn finish(&self) -> u32 {
+        !self.crc
+    }
+
+    /// Returns the checksum of `bytes`.
+    #[inline]
+    pub fn checksum(bytes: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(bytes);
+        crc.finish()
+    }
+}
+
+impl fmt::Write for Crc32 {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.update(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a over text written through `fmt::Write`. Unlike `FxHasher`
+/// its output does not depend on the platform or on std's `Hash` impls.
+struct Fnv64 {
+    hash: u64,
+}
+
+impl fmt::Write for Fnv64 {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &b in s.as_bytes() {
+            self.hash = (self.hash ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(())
+    }
+}
+
+/// Hash of one resting order's state, combined by XOR into the book hash.
+/// Hashes `id:side:price:remaining` as text, so it is stable across platforms
+/// and toolchains given stable `Display` impls. The final mix (splitmix64)
+/// spreads small differences across all bits so XOR-ing many of them does not
+/// cancel out.
+#[inline]
+pub(crate) fn order_hash<O: OrderInterface>(order: &O, remaining: O::N) -> u64 {
+    let mut hasher = Fnv64 {
+        hash: 0xcbf2_9ce4_8422_2325,
+    };
+    let side = if order.is_buy() { 'b' } else { 's' };
+    let _ = write!(
+        hasher,
+        "{}:{side}:{}:{remaining}",
+        order.id(),
+        order.price()
+    );
+    let mut z = hasher.hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::TestOrder;
+
+    #[test]
+    fn test_crc32_known_values() {
+        assert_eq!(Crc32::checksum(b""), 0);
+        assert_eq!(Crc32::checksum(b"123456789"), 0xcbf4_3926);
+        assert_eq!(
+            Crc32::checksum(b"The quick brown fox jumps over the lazy dog"),
+            0x414f_a339
+        );
+    }
+
+    #[test]
+    fn test_crc32_streaming() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        write!(crc, "{}{}", 56, 789).unwrap();
+        assert_eq!(crc.finish(), Crc32::checksum(b"123456789"));
+    }
+
+    #[test]
+    fn test_order_hash() {
+        let order = TestOrder::new("1", true, 100, 50);
+        assert_eq!(order_hash(&order, 50), order_hash(&order, 50));
+        assert_ne!(order_hash(&order, 50), order_hash(&order, 40));
+        let other = TestOrder::new("1", false, 100, 50);
+        assert_ne!(order_hash(&order, 50), order_hash(&other, 50));
+        // Pinned so a change to the encoding cannot go unnoticed.
+        assert_eq!(order_hash(&order, 50), 0xa8e4_f1ac_e427_892d);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::order::OrderInterface;
+
+/// An aggregated price level with running totals from the top of the book.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O::N: serde::Serialize",
+        deserialize = "O::N: serde::Deserialize<'de>"
+    ))
+)]
+pub struct DepthLevel<O: OrderInterface> {
+    pub price: O::N,
+    pub quantity: O::N,
+    /// Number of resting orders at this price.
+    pub orders: usize,
+    /// Sum of quantity from the best level up to and including this one.
+    pub cumulative_quantity: O::N,
+    /// Sum of price * quantity from the best level up to and including this one.
+    pub cumulative_notional: O::N,
+}
+
+impl<O: OrderInterface> Clone for DepthLevel<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for DepthLevel<O> {}
+
+/// A Level 2 snapshot of both sides. Reuse across calls to avoid reallocating.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O::N: serde::Serialize",
+        deserialize = "O::N: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Depth<O: OrderInterface> {
+    /// Highest price first.
+    pub bids: Vec<DepthLevel<O>>,
+    /// Lowest price first.
+    pub asks: Vec<DepthLevel<O>>,
+}
+
+impl<O: OrderInterface> Default for Depth<O> {
+    fn default() -> Self {
+        Self {
+            bids: Vec::new(),
+            asks: Vec::new(),
+        }
+    }
+}
+
+impl<O: OrderInterface> Depth<O> {
+    /// Creates a snapshot with room for `n` levels per side.
+    #[inline]
+    pub fn with_capacity(n: usize) -> Self {
+        Self {
+            bids: Vec::with_capacity(n),
+            asks: Vec::with_capacity(n),
+        }
+    }
+}
+
+/// A resting order with its FIFO position within its price level.
+pub struct DepthOrder<'a, O: OrderInterface> {
+    pub price: O::N,
+    /// Number of orders ahead of this one at the same price.
+    pub position: usize,
+    pub order: &'a O,
+}
+
+impl<O: OrderInterface> Clone for DepthOrder<'_, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for DepthOrder<'_, O> {}
+
+/// A Level 3 snapshot of both sides, every order in priority order.
+pub struct OrderDepth<'a, O: OrderInterface> {
+    /// Highest price first, then FIFO within each level.
+    pub bids: Vec<DepthOrder<'a, O>>,
+    /// Lowest price first, then FIFO within each level.
+    pub asks: Vec<DepthOrder<'a, O>>,
+}
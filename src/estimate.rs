@@ -0,0 +1,36 @@
+use crate::order::OrderInterface;
+
+/// Outcome of sweeping one side of the book with a hypothetical order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FillEstimate<O: OrderInterface> {
+    /// Quantity that would fill. Less than requested if liquidity runs out.
+    pub filled: O::N,
+    /// Sum of price * quantity over every fill.
+    pub notional: O::N,
+    /// Price of the last level touched, if anything filled.
+    pub worst_price: Option<O::N>,
+    /// Number of levels touched, including a partially consumed last level.
+    pub levels: usize,
+}
+
+impl<O: OrderInterface> Clone for FillEstimate<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for FillEstimate<O> {}
+
+impl<O: OrderInterface> FillEstimate<O> {
+    /// Volume-weighted fill price, truncated towards zero for integer `O::N`.
+    #[inline]
+    pub fn average_price(&self) -> Option<O::N> {
+        (self.filled > O::N::default()).then(|| self.notional / self.filled)
+    }
+
+    /// Returns true if the full requested quantity would fill.
+    #[inline]
+    pub fn is_complete(&self, quantity: O::N) -> bool {
+        self.filled == quantity
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    ob::{ApplyError, Instruction, Op, OrderBook},
+    order::OrderInterface,
+};
+
+/// Reusable output of `OrderBook::eval_into`. Keeps its capacity across calls,
+/// so once it has grown to fit a workload, evaluating allocates nothing as long
+/// as cloning an id does not. Ids are cloned into every instruction and match,
+/// so `String` ids still allocate.
+pub struct EvalBuffer<O: OrderInterface> {
+    instructions: Vec<Instruction<O>>,
+    /// Takers as (id, quantity taken, end of their makers in `makers`).
+    takers: Vec<(O::T, O::N, usize)>,
+    makers: Vec<(O::T, O::N)>,
+}
+
+/// A match in an `EvalBuffer`, borrowed rather than owning its makers.
+pub struct MatchRef<'a, O: OrderInterface> {
+    pub taker: (&'a O::T, O::N),
+    pub makers: &'a [(O::T, O::N)],
+}
+
+impl<O: OrderInterface> EvalBuffer<O> {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+            takers: Vec::new(),
+            makers: Vec::new(),
+        }
+    }
+
+    /// Empties the buffer, keeping its capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.instructions.clear();
+        self.takers.clear();
+        self.makers.clear();
+    }
+
+    #[inline]
+    pub fn instructions(&self) -> &[Instruction<O>] {
+        &self.instructions
+    }
+
+    /// Matches in op order, as `eval` would return them.
+    #[inline]
+    pub fn matches(&self) -> impl Iterator<Item = MatchRef<'_, O>> {
+        let mut start = 0;
+        self.takers.iter().map(move |(id, quantity, end)| {
+            let makers = &self.makers[start..*end];
+            start = *end;
+            MatchRef {
+                taker: (id, *quantity),
+                makers,
+            }
+        })
+    }
+}
+
+impl<O: OrderInterface> Default for EvalBuffer<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: OrderInterface> OrderBook<O> {
+    /// Like `eval`, but writes the matches and instructions into `out` instead of
+    /// allocating them. Clears `out` first; its capacity is reused.
+    #[inline]
+    pub fn eval_into(&mut self, ops: impl IntoIterator<Item = Op<O>>, out: &mut EvalBuffer<O>) {
+        out.clear();
+        for op in ops {
+            match op {
+                Op::Insert(order) => {
+                    let taker = self.eval_insert_to(order, &mut out.instructions, &mut out.makers);
+                    if let Some((id, quantity)) = taker {
+                        out.takers.push((id, quantity, out.makers.len()));
+                    }
+                }
+                Op::Delete(order_id) => out.instructions.push(self.eval_cancel(order_id)),
+            }
+        }
+    }
+
+    /// Applies the instructions in `buffer` like `apply`, then clears it for the
+    /// next `eval_into`.
+    #[inline]
+    pub fn apply_buffer(&mut self, buffer: &mut EvalBuffer<O>) -> Result<(), ApplyError<O>> {
+        let result = self.apply_to(buffer.instructions.drain(..), &mut ());
+        buffer.clear();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ob::Msg, order::TestOrder};
+
+    fn ops() -> Vec<Op<TestOrder>> {
+        vec![
+            Op::Insert(TestOrder::new("b1", true, 101, 15)),
+            Op::Insert(TestOrder::new("b2", true, 99, 5)),
+            Op::Delete(String::from("s2")),
+            Op::Insert(TestOrder::new("b3", true, 101, 8)),
+            Op::Delete(String::from("x")),
+        ]
+    }
+
+    fn book() -> OrderBook<TestOrder> {
+        let mut ob = OrderBook::default();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 100, 10)),
+            Op::Insert(TestOrder::new("s2", false, 101, 10)),
+            Op::Insert(TestOrder::new("s3", false, 101, 4)),
+        ]);
+        ob.apply(instructions).unwrap();
+        ob
+    }
+
+    #[test]
+    fn test_eval_into_matches_eval() {
+        let mut expected_ob = book();
+        let (matches, instructions) = expected_ob.eval(ops());
+
+        // Stale contents are cleared
+        let mut ob = book();
+        let mut out = EvalBuffer::new();
+        out.instructions.push(Instruction::NoOp(Msg::OrderNotFound));
+        ob.eval_into(ops(), &mut out);
+        assert_eq!(out.instructions(), instructions);
+        let buffered: Vec<_> = out
+            .matches()
+            .map(|m| ((m.taker.0.clone(), m.taker.1), m.makers.to_vec()))
+            .collect();
+        let expected: Vec<_> = matches.into_iter().map(|m| (m.taker, m.makers)).collect();
+        assert_eq!(buffered, expected);
+        assert_eq!(buffered.len(), 2);
+
+        expected_ob.apply(instructions).unwrap();
+        ob.apply_buffer(&mut out).unwrap();
+        assert!(out.instructions().is_empty());
+        assert_eq!(out.matches().count(), 0);
+        assert_eq!(ob.compute_hash(), expected_ob.compute_hash());
+    }
+
+    #[test]
+    fn test_buffer_keeps_capacity() {
+        let mut ob = book();
+        let mut out = EvalBuffer::new();
+        ob.eval_into(ops(), &mut out);
+        let capacity = out.instructions.capacity();
+        ob.apply_buffer(&mut out).unwrap();
+        assert_eq!(out.instructions.capacity(), capacity);
+
+        ob.eval_into([Op::Insert(TestOrder::new("s4", false, 105, 1))], &mut out);
+        assert_eq!(out.instructions().len(), 1);
+        assert_eq!(out.instructions.capacity(), capacity);
+    }
+}
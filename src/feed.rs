@@ -0,0 +1,229 @@
+use crate::{level::Level, order::OrderInterface};
+
+/// A change to an aggregated price level, for publishing incremental L2 updates.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LevelUpdate<O: OrderInterface> {
+    /// A new price level was created.
+    Add {
+        is_bid: bool,
+        price: O::N,
+        quantity: O::N,
+        orders: usize,
+    },
+    /// An existing level's quantity or order count changed.
+    Change {
+        is_bid: bool,
+        price: O::N,
+        quantity: O::N,
+        orders: usize,
+    },
+    /// The last order at this price left the book.
+    Remove { is_bid: bool, price: O::N },
+}
+
+/// Receives each state change made while applying instructions.
+/// `level` is the order's level after the change, or None if it was removed.
+pub(crate) trait Sink<O: OrderInterface> {
+    /// False for sinks that discard everything, so callers can skip the lookups.
+    const ENABLED: bool = true;
+
+    fn insert(&mut self, order: &O, level: &Level<O>);
+    fn fill(&mut self, order: &O, quantity: O::N, level: Option<&Level<O>>);
+    fn reduce(&mut self, order: &O, quantity: O::N, level: Option<&Level<O>>);
+    fn delete(&mut self, order: &O, level: Option<&Level<O>>);
+}
+
+impl<O: OrderInterface> Sink<O> for () {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn insert(&mut self, _: &O, _: &Level<O>) {}
+
+    #[inline(always)]
+    fn fill(&mut self, _: &O, _: O::N, _: Option<&Level<O>>) {}
+
+    #[inline(always)]
+    fn reduce(&mut self, _: &O, _: O::N, _: Option<&Level<O>>) {}
+
+    #[inline(always)]
+    fn delete(&mut self, _: &O, _: Option<&Level<O>>) {}
+}
+
+impl<O: OrderInterface> Sink<O> for Vec<LevelUpdate<O>> {
+    #[inline]
+    fn insert(&mut self, order: &O, level: &Level<O>) {
+        let (is_bid, price, quantity, orders) = (
+            order.is_buy(),
+            level.price(),
+            level.total_quantity(),
+            level.len(),
+        );
+        self.push(if orders == 1 {
+            LevelUpdate::Add {
+                is_bid,
+                price,
+                quantity,
+                orders,
+            }
+        } else {
+            LevelUpdate::Change {
+                is_bid,
+                price,
+                quantity,
+                orders,
+            }
+        });
+    }
+
+    #[inline]
+    fn fill(&mut self, order: &O, _: O::N, level: Option<&Level<O>>) {
+        self.delete(order, level);
+    }
+
+    #[inline]
+    fn reduce(&mut self, order: &O, _: O::N, level: Option<&Level<O>>) {
+        self.delete(order, level);
+    }
+
+    #[inline]
+    fn delete(&mut self, order: &O, level: Option<&Level<O>>) {
+        let is_bid = order.is_buy();
+        self.push(match level {
+            Some(level) => LevelUpdate::Change {
+                is_bid,
+                price: level.price(),
+                quantity: level.total_quantity(),
+                orders: level.len(),
+            },
+            None => LevelUpdate::Remove {
+                is_bid,
+                price: order.price(),
+            },
+        });
+    }
+}
+
+/// A market-by-order event, shaped like an ITCH message. `sequence` starts at 1
+/// and increases by one per event.
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrderEvent<O: OrderInterface> {
+    /// An order was added to the back of its level with `quantity` resting.
+    Add {
+        sequence: u64,
+        id: O::T,
+        is_buy: bool,
+        price: O::N,
+        quantity: O::N,
+    },
+    /// A resting order traded. It leaves the book when `remaining` is zero.
+    Execute {
+        sequence: u64,
+        id: O::T,
+        price: O::N,
+        quantity: O::N,
+        remaining: O::N,
+    },
+    /// A resting order was reduced by `Instruction::Reduce`, keeping its place in
+    /// the queue. It leaves the book when `remaining` is zero.
+    Reduce {
+        sequence: u64,
+        id: O::T,
+        quantity: O::N,
+        remaining: O::N,
+    },
+    /// A resting order was cancelled.
+    Delete { sequence: u64, id: O::T },
+}
+
+impl<O: OrderInterface> OrderEvent<O> {
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        match self {
+            OrderEvent::Add { sequence, .. }
+            | OrderEvent::Execute { sequence, .. }
+            | OrderEvent::Reduce { sequence, .. }
+            | OrderEvent::Delete { sequence, .. } => *sequence,
+        }
+    }
+}
+
+/// Collects market-by-order events, numbering them across `apply` calls.
+pub struct OrderFeed<O: OrderInterface> {
+    sequence: u64,
+    pub events: Vec<OrderEvent<O>>,
+}
+
+impl<O: OrderInterface> Default for OrderFeed<O> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<O: OrderInterface> OrderFeed<O> {
+    /// Creates a feed whose next event is numbered `sequence + 1`.
+    #[inline]
+    pub fn new(sequence: u64) -> Self {
+        Self {
+            sequence,
+            events: Vec::new(),
+        }
+    }
+
+    /// Returns the sequence number of the last event produced.
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    #[inline(always)]
+    fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+}
+
+impl<O: OrderInterface> Sink<O> for OrderFeed<O> {
+    #[inline]
+    fn insert(&mut self, order: &O, _: &Level<O>) {
+        let sequence = self.next_sequence();
+        self.events.push(OrderEvent::Add {
+            sequence,
+            id: order.id().clone(),
+            is_buy: order.is_buy(),
+            price: order.price(),
+            quantity: order.remaining(),
+        });
+    }
+
+    #[inline]
+    fn fill(&mut self, order: &O, quantity: O::N, _: Option<&Level<O>>) {
+        let sequence = self.next_sequence();
+        self.events.push(OrderEvent::Execute {
+            sequence,
+            id: order.id().clone(),
+            price: order.price(),
+            quantity,
+            remaining: order.remaining(),
+        });
+    }
+
+    #[inline]
+    fn reduce(&mut self, order: &O, quantity: O::N, _: Option<&Level<O>>) {
+        let sequence = self.next_sequence();
+        self.events.push(OrderEvent::Reduce {
+            sequence,
+            id: order.id().clone(),
+            quantity,
+            remaining: order.remaining(),
+        });
+    }
+
+    #[inline]
+    fn delete(&mut self, order: &O, _: Option<&Level<O>>) {
+        let sequence = self.next_sequence();
+        self.events.push(OrderEvent::Delete {
+            sequence,
+            id: order.id().clone(),
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops::{AddAssign, SubAssign};
+
+/// Fenwick (binary indexed) tree over a growable sequence of values: appends,
+/// decrements and prefix sums in O(log n). Values must stay non-negative, so
+/// unsigned types work.
+#[derive(Clone, Default)]
+pub(crate) struct Fenwick<V> {
+    /// `tree[i]` holds the sum of the values in `(i + 1 - lowbit(i + 1), i]`.
+    tree: Vec<V>,
+}
+
+impl<V: Copy + Default + AddAssign + SubAssign> Fenwick<V> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self { tree: Vec::new() }
+    }
+
+    /// Values appended so far, including ones decremented to zero.
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Appends `value` at index `len()`.
+    #[inline]
+    pub(crate) fn push(&mut self, value: V) {
+        let at = self.tree.len() + 1;
+        let mut sum = value;
+        // The new node covers its children at at - 1, at - 2, at - 4, ...
+        let mut step = 1;
+        while step < lowbit(at) {
+            sum += self.tree[at - step - 1];
+            step <<= 1;
+        }
+        self.tree.push(sum);
+    }
+
+    /// Subtracts `value` from the value at `index`.
+    #[inline]
+    pub(crate) fn sub(&mut self, index: usize, value: V) {
+        let mut at = index + 1;
+        while at <= self.tree.len() {
+            self.tree[at - 1] -= value;
+            at += lowbit(at);
+        }
+    }
+
+    /// Returns the sum of the values before `index`.
+    #[inline]
+    pub(crate) fn prefix(&self, index: usize) -> V {
+        let mut sum = V::default();
+        let mut at = index.min(self.tree.len());
+        while at > 0 {
+            sum += self.tree[at - 1];
+            at -= lowbit(at);
+        }
+        sum
+    }
+
+    /// Replaces the contents with `values`, in O(n).
+    pub(crate) fn rebuild(&mut self, values: impl IntoIterator<Item = V>) {
+        self.tree.clear();
+        self.tree.extend(values);
+        let len = self.tree.len();
+        for at in 1..=len {
+            let parent = at + lowbit(at);
+            if parent <= len {
+                let value = self.tree[at - 1];
+                self.tree[parent - 1] += value;
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn lowbit(at: usize) -> usize {
+    at & at.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_sub_prefix() {
+        let values = [5u64, 0, 3, 7, 1, 2, 9, 4, 6];
+        let mut fenwick = Fenwick::new();
+        for (i, &value) in values.iter().enumerate() {
+            fenwick.push(value);
+            let sums: Vec<u64> = (0..=i + 1).map(|j| fenwick.prefix(j)).collect();
+            let expected: Vec<u64> = (0..=i + 1).map(|j| values[..j].iter().sum()).collect();
+            assert_eq!(sums, expected);
+        }
+        fenwick.sub(3, 7);
+        fenwick.sub(0, 2);
+        assert_eq!(fenwick.prefix(1), 3);
+        assert_eq!(fenwick.prefix(4), 6);
+        assert_eq!(fenwick.prefix(9), 28);
+        assert_eq!(fenwick.prefix(100), 28);
+        assert_eq!(fenwick.len(), 9);
+
+        let mut rebuilt = Fenwick::new();
+        rebuilt.rebuild([3u64, 0, 3, 0, 1, 2, 9, 4, 6]);
+        assert_eq!(rebuilt.tree, fenwick.tree);
+        rebuilt.rebuild([]);
+        assert_eq!(rebuilt.len(), 0);
+        assert_eq!(rebuilt.prefix(3), 0);
+    }
+}
@@ -0,0 +1,763 @@
+use crate::{
+    hash::FxHashMap,
+    ob::{Instruction, Match, Msg, Op, OrderBook},
+    order::OrderInterface,
+};
+use std::fmt::{self, Write};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// FIX 4.4 tag=value
+//
+//   8=FIX.4.4 | 9=body length | 35=msg type | body fields | 10=checksum
+//
+// Fields end with SOH (0x01). The body length counts the bytes from 35= up to
+// and including the SOH before 10=, and the checksum is the sum of every byte
+// before 10= modulo 256, as three digits. Session-level fields (sequence
+// numbers, comp ids, sending times) are left to the session layer.
+// ─────────────────────────────────────────────────────────────────────────────
+
+const SOH: char = '\x01';
+const BEGIN_STRING: &str = "8=FIX.4.4\x01";
+
+const CUM_QTY: u32 = 14;
+const EXEC_ID: u32 = 17;
+const CL_ORD_ID: u32 = 11;
+const LAST_PX: u32 = 31;
+const LAST_QTY: u32 = 32;
+const MSG_TYPE: u32 = 35;
+const ORDER_ID: u32 = 37;
+const ORDER_QTY: u32 = 38;
+const ORD_STATUS: u32 = 39;
+const ORD_TYPE: u32 = 40;
+const ORIG_CL_ORD_ID: u32 = 41;
+const PRICE: u32 = 44;
+const SIDE: u32 = 54;
+const SYMBOL: u32 = 55;
+const TEXT: u32 = 58;
+const CXL_REJ_REASON: u32 = 102;
+const ORD_REJ_REASON: u32 = 103;
+const EXEC_TYPE: u32 = 150;
+const LEAVES_QTY: u32 = 151;
+const CXL_REJ_RESPONSE_TO: u32 = 434;
+
+// ExecType (150) values; OrdStatus (39) uses the same codes
+const NEW: char = '0';
+const PARTIALLY_FILLED: char = '1';
+const FILLED: char = '2';
+const CANCELED: char = '4';
+const REPLACED: char = '5';
+const REJECTED: char = '8';
+const TRADE: char = 'F';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    /// Not a sequence of tag=value fields each ending with SOH.
+    Malformed,
+    BodyLength {
+        declared: usize,
+        actual: usize,
+    },
+    Checksum {
+        expected: u8,
+        found: u8,
+    },
+    MissingField(u32),
+    /// A field has a value this codec does not accept.
+    BadField(u32),
+    UnsupportedMsgType(String),
+}
+
+impl fmt::Display for FixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixError::Malformed => write!(f, "malformed fix message"),
+            FixError::BodyLength { declared, actual } => {
+                write!(f, "fix body length is {actual}, declared {declared}")
+            }
+            FixError::Checksum { expected, found } => {
+                write!(f, "fix checksum is {found:03}, expected {expected:03}")
+            }
+            FixError::MissingField(tag) => write!(f, "fix message is missing tag {tag}"),
+            FixError::BadField(tag) => write!(f, "fix message has a bad value for tag {tag}"),
+            FixError::UnsupportedMsgType(t) => write!(f, "unsupported fix message type {t}"),
+        }
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// A FIX 4.4 message: its MsgType (35) and body fields in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixMessage {
+    msg_type: String,
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    #[inline]
+    pub fn new(msg_type: &str) -> Self {
+        Self {
+            msg_type: msg_type.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Appends a body field.
+    #[inline]
+    pub fn with(mut self, tag: u32, value: impl fmt::Display) -> Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> &str {
+        &self.msg_type
+    }
+
+    /// Returns the first value of a body field.
+    #[inline]
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[inline]
+    pub fn fields(&self) -> &[(u32, String)] {
+        &self.fields
+    }
+
+    /// Encodes the message with its header and trailer.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = format!("{MSG_TYPE}={}{SOH}", self.msg_type);
+        for (tag, value) in &self.fields {
+            let _ = write!(body, "{tag}={value}{SOH}");
+        }
+        let mut out = format!("{BEGIN_STRING}9={}{SOH}{body}", body.len()).into_bytes();
+        let checksum = checksum(&out);
+        out.extend_from_slice(format!("10={checksum:03}{SOH}").as_bytes());
+        out
+    }
+
+    /// Parses one whole message, verifying its body length and checksum.
+    pub fn parse(bytes: &[u8]) -> Result<Self, FixError> {
+        let text = std::str::from_utf8(bytes).map_err(|_| FixError::Malformed)?;
+        let rest = text
+            .strip_prefix(BEGIN_STRING)
+            .ok_or(FixError::BadField(8))?;
+        let (declared, rest) = rest.split_once(SOH).ok_or(FixError::Malformed)?;
+        let declared = declared
+            .strip_prefix("9=")
+            .ok_or(FixError::MissingField(9))?;
+        let declared = declared.parse().map_err(|_| FixError::BadField(9))?;
+
+        let trailer = text.rfind("\x0110=").ok_or(FixError::MissingField(10))? + 1;
+        let body_start = text.len() - rest.len();
+        let actual = trailer.saturating_sub(body_start);
+        if actual != declared {
+            return Err(FixError::BodyLength { declared, actual });
+        }
+        let found = text[trailer + 3..]
+            .strip_suffix(SOH)
+            .filter(|v| v.len() == 3)
+            .and_then(|v| v.parse().ok())
+            .ok_or(FixError::BadField(10))?;
+        let expected = checksum(&bytes[..trailer]);
+        if found != expected {
+            return Err(FixError::Checksum { expected, found });
+        }
+
+        // An empty body has no MsgType to split off
+        if trailer <= body_start {
+            return Err(FixError::MissingField(MSG_TYPE));
+        }
+        let mut fields = text[body_start..trailer - 1].split(SOH).map(|field| {
+            let (tag, value) = field.split_once('=').ok_or(FixError::Malformed)?;
+            let tag = tag.parse::<u32>().map_err(|_| FixError::Malformed)?;
+            Ok((tag, value.to_string()))
+        });
+        let (MSG_TYPE, msg_type) = fields.next().ok_or(FixError::Malformed)?? else {
+            return Err(FixError::MissingField(MSG_TYPE));
+        };
+        Ok(Self {
+            msg_type,
+            fields: fields.collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[inline]
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Converts between FIX field values and the book's order types. Ids are written
+/// back out with their `Display` impl.
+pub trait FixCodec<O: OrderInterface> {
+    /// Decodes a ClOrdID (11) or OrigClOrdID (41).
+    fn decode_id(&self, value: &str) -> Option<O::T>;
+
+    fn decode_price(&self, value: &str) -> Option<O::N>;
+
+    fn decode_quantity(&self, value: &str) -> Option<O::N>;
+
+    fn encode_price(&self, price: O::N) -> String;
+
+    fn encode_quantity(&self, quantity: O::N) -> String;
+
+    /// Builds a limit order with nothing filled yet.
+    fn order(&self, id: O::T, is_buy: bool, price: O::N, quantity: O::N) -> O;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixRequestKind {
+    /// NewOrderSingle (D).
+    New,
+    /// OrderCancelRequest (F).
+    Cancel,
+    /// OrderCancelReplaceRequest (G).
+    Replace,
+}
+
+/// The parts of a client request that its reports refer back to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixRequest<O: OrderInterface> {
+    pub kind: FixRequestKind,
+    pub cl_ord_id: String,
+    pub orig_cl_ord_id: Option<String>,
+    pub is_buy: bool,
+    /// Quantity the new or replacing order is evaluated with. Zero for cancels.
+    pub quantity: O::N,
+}
+
+/// Translates client requests into `Op`s and their results into reports.
+///
+/// For each request: `decode` it, `eval` the ops, pass the results to `reports`,
+/// then `apply` the instructions. `reports` reads fill prices and maker state
+/// from the book, so it must see the book as it was when the request was evaluated.
+pub struct FixTranslator<O: OrderInterface, C> {
+    codec: C,
+    symbol: String,
+    exec_id: u64,
+    /// Quantity executed under the ClOrdIDs an order had before being replaced.
+    carried: FxHashMap<O::T, O::N>,
+}
+
+impl<O: OrderInterface, C: FixCodec<O>> FixTranslator<O, C> {
+    /// Creates a translator for a book trading `symbol` (55).
+    #[inline]
+    pub fn new(codec: C, symbol: &str) -> Self {
+        Self {
+            codec,
+            symbol: symbol.to_string(),
+            exec_id: 0,
+            carried: FxHashMap::default(),
+        }
+    }
+
+    #[inline]
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Decodes a NewOrderSingle, OrderCancelRequest or OrderCancelReplaceRequest.
+    ///
+    /// A replace cancels the original order and enters a new one for the quantity
+    /// not yet executed. It only cancels if nothing is left, and only inserts (to
+    /// be rejected as a duplicate) if its ClOrdID is already resting.
+    pub fn decode(
+        &self,
+        book: &OrderBook<O>,
+        message: &FixMessage,
+    ) -> Result<(FixRequest<O>, Vec<Op<O>>), FixError> {
+        let field = |tag| message.get(tag).ok_or(FixError::MissingField(tag));
+        let id = |tag| {
+            let value = field(tag)?;
+            let id = self.codec.decode_id(value).ok_or(FixError::BadField(tag))?;
+            Ok::<_, FixError>((value.to_string(), id))
+        };
+        let kind = match message.msg_type() {
+            "D" => FixRequestKind::New,
+            "F" => FixRequestKind::Cancel,
+            "G" => FixRequestKind::Replace,
+            other => return Err(FixError::UnsupportedMsgType(other.to_string())),
+        };
+        if field(SYMBOL)? != self.symbol {
+            return Err(FixError::BadField(SYMBOL));
+        }
+        let is_buy = match field(SIDE)? {
+            "1" => true,
+            "2" => false,
+            _ => return Err(FixError::BadField(SIDE)),
+        };
+        let (cl_ord_id, order_id) = id(CL_ORD_ID)?;
+        let mut request = FixRequest {
+            kind,
+            cl_ord_id,
+            orig_cl_ord_id: None,
+            is_buy,
+            quantity: O::N::default(),
+        };
+
+        if kind == FixRequestKind::New {
+            let (price, quantity) = self.limit(message)?;
+            request.quantity = quantity;
+            let order = self.codec.order(order_id, is_buy, price, quantity);
+            return Ok((request, vec![Op::Insert(order)]));
+        }
+
+        let (orig_cl_ord_id, orig_id) = id(ORIG_CL_ORD_ID)?;
+        request.orig_cl_ord_id = Some(orig_cl_ord_id);
+        let resting = book.order(&orig_id);
+        if let Some(order) = resting
+            && order.is_buy() != is_buy
+        {
+            return Err(FixError::BadField(SIDE));
+        }
+        if kind == FixRequestKind::Cancel {
+            return Ok((request, vec![Op::Delete(orig_id)]));
+        }
+
+        let (price, quantity) = self.limit(message)?;
+        let Some(resting) = resting else {
+            return Ok((request, vec![Op::Delete(orig_id)]));
+        };
+        let executed = self.executed(resting);
+        if quantity > executed {
+            request.quantity = quantity - executed;
+        }
+        let order = self.codec.order(order_id, is_buy, price, request.quantity);
+        let ops = if book.order(order.id()).is_some() {
+            vec![Op::Insert(order)]
+        } else if request.quantity == O::N::default() {
+            vec![Op::Delete(orig_id)]
+        } else {
+            vec![Op::Delete(orig_id), Op::Insert(order)]
+        };
+        Ok((request, ops))
+    }
+
+    /// Builds the ExecutionReports (8) or OrderCancelReject (9) for a decoded
+    /// request, from what `eval` returned for its ops.
+    pub fn reports(
+        &mut self,
+        book: &OrderBook<O>,
+        request: &FixRequest<O>,
+        matches: &[Match<O>],
+        instructions: &[Instruction<O>],
+    ) -> Vec<FixMessage> {
+        let zero = O::N::default();
+        let orig = request.orig_cl_ord_id.as_deref();
+        let orig_order = orig
+            .and_then(|orig| self.codec.decode_id(orig))
+            .and_then(|id| book.order(&id));
+        let rejection = instructions.iter().find_map(|i| match i {
+            Instruction::NoOp(msg) => Some(msg),
+            _ => None,
+        });
+
+        if let Some(msg) = rejection {
+            let text = match msg {
+                Msg::OrderNotFound => "unknown order",
+                Msg::OrderAlreadyExists => "duplicate ClOrdID",
+            };
+            if request.kind == FixRequestKind::New {
+                let report = self
+                    .report(&request.cl_ord_id, request.is_buy, REJECTED, zero, zero)
+                    .with(ORD_REJ_REASON, 6)
+                    .with(TEXT, text);
+                return vec![report];
+            }
+            let (order_id, status) = match orig_order {
+                Some(order) => (order.id().to_string(), self.status(order)),
+                None => (String::from("NONE"), REJECTED),
+            };
+            let response_to = if request.kind == FixRequestKind::Cancel {
+                1
+            } else {
+                2
+            };
+            let reason = if *msg == Msg::OrderNotFound { 1 } else { 6 };
+            let reject = FixMessage::new("9")
+                .with(ORDER_ID, order_id)
+                .with(CL_ORD_ID, &request.cl_ord_id)
+                .with(ORIG_CL_ORD_ID, orig.unwrap_or_default())
+                .with(ORD_STATUS, status)
+                .with(CXL_REJ_RESPONSE_TO, response_to)
+                .with(CXL_REJ_REASON, reason)
+                .with(TEXT, text);
+            return vec![reject];
+        }
+
+        let mut reports = Vec::new();
+        match (request.kind, orig_order) {
+            (FixRequestKind::New, _) => {
+                let report = self.report(
+                    &request.cl_ord_id,
+                    request.is_buy,
+                    NEW,
+                    zero,
+                    request.quantity,
+                );
+                reports.push(report);
+            }
+            (FixRequestKind::Cancel, Some(order)) => {
+                let executed = self.executed(order);
+                self.carried.remove(order.id());
+                let report = self
+                    .report(&request.cl_ord_id, request.is_buy, CANCELED, executed, zero)
+                    .with(ORIG_CL_ORD_ID, orig.unwrap_or_default());
+                reports.push(report);
+            }
+            (FixRequestKind::Replace, Some(order)) => {
+                let cum = self.executed(order);
+                self.carried.remove(order.id());
+                if cum > zero
+                    && request.quantity > zero
+                    && let Some(id) = self.codec.decode_id(&request.cl_ord_id)
+                {
+                    self.carried.insert(id, cum);
+                }
+                let report = self
+                    .report(
+                        &request.cl_ord_id,
+                        request.is_buy,
+                        REPLACED,
+                        cum,
+                        request.quantity,
+                    )
+                    .with(ORIG_CL_ORD_ID, orig.unwrap_or_default());
+                reports.push(report);
+            }
+            // A found order always has a Delete; a missing one a NoOp
+            _ => return reports,
+        }
+
+        for report in book.reports(matches, instructions) {
+            let (Some(id), Some((price, quantity))) = (report.id, report.fill) else {
+                continue;
+            };
+            // Makers are resting, the taker is not yet
+            let is_buy = book
+                .order(&id)
+                .map_or(request.is_buy, |maker| maker.is_buy());
+            let carried = self.carried.get(&id).copied().unwrap_or_default();
+            let cum = carried + report.cumulative;
+            let message = self.report(&id.to_string(), is_buy, TRADE, cum, report.leaves);
+            reports.push(self.fill(message, price, quantity));
+            if report.leaves == zero {
+                self.carried.remove(&id);
+            }
+        }
+        reports
+    }
+
+    /// Returns (price, quantity) of a limit order request.
+    fn limit(&self, message: &FixMessage) -> Result<(O::N, O::N), FixError> {
+        let field = |tag| message.get(tag).ok_or(FixError::MissingField(tag));
+        if field(ORD_TYPE)? != "2" {
+            return Err(FixError::BadField(ORD_TYPE));
+        }
+        let price = self.codec.decode_price(field(PRICE)?);
+        let price = price.ok_or(FixError::BadField(PRICE))?;
+        let quantity = self.codec.decode_quantity(field(ORDER_QTY)?);
+        let quantity = quantity
+            .filter(|q| *q > O::N::default())
+            .ok_or(FixError::BadField(ORDER_QTY))?;
+        Ok((price, quantity))
+    }
+
+    /// Quantity executed over the life of an order, across replaces.
+    #[inline]
+    fn executed(&self, order: &O) -> O::N {
+        let carried = self.carried.get(order.id()).copied().unwrap_or_default();
+        carried + order.quantity() - order.remaining()
+    }
+
+    #[inline]
+    fn status(&self, order: &O) -> char {
+        if self.executed(order) > O::N::default() {
+            PARTIALLY_FILLED
+        } else {
+            NEW
+        }
+    }
+
+    fn report(
+        &mut self,
+        cl_ord_id: &str,
+        is_buy: bool,
+        exec_type: char,
+        cum: O::N,
+        leaves: O::N,
+    ) -> FixMessage {
+        let zero = O::N::default();
+        let status = match exec_type {
+            REJECTED | CANCELED => exec_type,
+            _ if leaves == zero => FILLED,
+            _ if cum > zero => PARTIALLY_FILLED,
+            _ => NEW,
+        };
+        self.exec_id += 1;
+        FixMessage::new("8")
+            .with(ORDER_ID, cl_ord_id)
+            .with(CL_ORD_ID, cl_ord_id)
+            .with(EXEC_ID, self.exec_id)
+            .with(EXEC_TYPE, exec_type)
+            .with(ORD_STATUS, status)
+            .with(SYMBOL, &self.symbol)
+            .with(SIDE, if is_buy { '1' } else { '2' })
+            .with(LEAVES_QTY, self.codec.encode_quantity(leaves))
+            .with(CUM_QTY, self.codec.encode_quantity(cum))
+    }
+
+    #[inline]
+    fn fill(&self, report: FixMessage, price: O::N, quantity: O::N) -> FixMessage {
+        report
+            .with(LAST_QTY, self.codec.encode_quantity(quantity))
+            .with(LAST_PX, self.codec.encode_price(price))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::{TestCodec, TestOrder};
+
+    type Translator = FixTranslator<TestOrder, TestCodec>;
+
+    fn order(
+        msg_type: &str,
+        id: &str,
+        orig: Option<&str>,
+        side: u8,
+        price: u64,
+        qty: u64,
+    ) -> FixMessage {
+        let mut message = FixMessage::new(msg_type).with(CL_ORD_ID, id);
+        if let Some(orig) = orig {
+            message = message.with(ORIG_CL_ORD_ID, orig);
+        }
+        message
+            .with(SYMBOL, "ACME")
+            .with(SIDE, side)
+            .with(ORD_TYPE, 2)
+            .with(PRICE, price)
+            .with(ORDER_QTY, qty)
+    }
+
+    /// Runs a request through decode, eval, reports and apply.
+    fn send(
+        fix: &mut Translator,
+        ob: &mut OrderBook<TestOrder>,
+        message: FixMessage,
+    ) -> Vec<FixMessage> {
+        // Requests travel over the wire
+        let message = FixMessage::parse(&message.encode()).unwrap();
+        let (request, ops) = fix.decode(ob, &message).unwrap();
+        let (matches, instructions) = ob.eval(ops);
+        let reports = fix.reports(ob, &request, &matches, &instructions);
+        ob.apply(instructions).unwrap();
+        reports
+    }
+
+    fn fields(message: &FixMessage, tags: &[u32]) -> Vec<String> {
+        let mut values = vec![message.msg_type().to_string()];
+        values.extend(
+            tags.iter()
+                .map(|t| message.get(*t).unwrap_or("-").to_string()),
+        );
+        values
+    }
+
+    const SUMMARY: &[u32] = &[
+        CL_ORD_ID, EXEC_TYPE, ORD_STATUS, SIDE, LAST_QTY, LAST_PX, CUM_QTY, LEAVES_QTY,
+    ];
+
+    #[test]
+    fn test_message_roundtrip() {
+        let message = FixMessage::new("F")
+            .with(CL_ORD_ID, "c1")
+            .with(ORIG_CL_ORD_ID, "a1");
+        let bytes = message.encode();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap().replace(SOH, "|"),
+            "8=FIX.4.4|9=17|35=F|11=c1|41=a1|10=085|"
+        );
+        assert_eq!(FixMessage::parse(&bytes), Ok(message));
+        assert_eq!(
+            FixMessage::parse(&bytes).unwrap().get(ORIG_CL_ORD_ID),
+            Some("a1")
+        );
+
+        let mut bad = bytes.clone();
+        let last = bad.len() - 2;
+        bad[last] = b'4';
+        assert_eq!(
+            FixMessage::parse(&bad),
+            Err(FixError::Checksum {
+                expected: 85,
+                found: 84
+            })
+        );
+        let text = String::from_utf8(bytes).unwrap().replace("9=17", "9=16");
+        assert_eq!(
+            FixMessage::parse(text.as_bytes()),
+            Err(FixError::BodyLength {
+                declared: 16,
+                actual: 17
+            })
+        );
+        assert_eq!(
+            FixMessage::parse(b"8=FIX.4.2\x019=5\x01"),
+            Err(FixError::BadField(8))
+        );
+
+        let mut empty = format!("{BEGIN_STRING}9=0{SOH}").into_bytes();
+        let sum = checksum(&empty);
+        empty.extend_from_slice(format!("10={sum:03}{SOH}").as_bytes());
+        assert_eq!(
+            FixMessage::parse(&empty),
+            Err(FixError::MissingField(MSG_TYPE))
+        );
+    }
+
+    #[test]
+    fn test_new_order_reports() {
+        let mut fix = Translator::new(TestCodec, "ACME");
+        let mut ob = OrderBook::default();
+        let reports = send(&mut fix, &mut ob, order("D", "s1", None, 2, 100, 10));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            fields(&reports[0], SUMMARY),
+            ["8", "s1", "0", "0", "2", "-", "-", "0", "10"]
+        );
+        send(&mut fix, &mut ob, order("D", "s2", None, 2, 101, 10));
+
+        let reports = send(&mut fix, &mut ob, order("D", "b1", None, 1, 101, 15));
+        let reports: Vec<_> = reports.iter().map(|r| fields(r, SUMMARY)).collect();
+        assert_eq!(
+            reports,
+            [
+                ["8", "b1", "0", "0", "1", "-", "-", "0", "15"],
+                ["8", "b1", "F", "1", "1", "10", "100", "10", "5"],
+                ["8", "s1", "F", "2", "2", "10", "100", "10", "0"],
+                ["8", "b1", "F", "2", "1", "5", "101", "15", "0"],
+                ["8", "s2", "F", "1", "2", "5", "101", "5", "5"],
+            ]
+        );
+
+        let reports = send(&mut fix, &mut ob, order("D", "s2", None, 2, 105, 1));
+        assert_eq!(
+            fields(&reports[0], &[EXEC_TYPE, ORD_STATUS, ORD_REJ_REASON]),
+            ["8", "8", "8", "6"]
+        );
+        assert_eq!(ob.order(&String::from("s2")).unwrap().remaining(), 5);
+
+        let wrong = FixMessage::new("D")
+            .with(CL_ORD_ID, "x")
+            .with(SYMBOL, "OTHER")
+            .with(SIDE, 2);
+        assert_eq!(
+            fix.decode(&ob, &wrong).err(),
+            Some(FixError::BadField(SYMBOL))
+        );
+        let market = FixMessage::new("D")
+            .with(CL_ORD_ID, "x")
+            .with(SYMBOL, "ACME")
+            .with(SIDE, 1)
+            .with(ORD_TYPE, 1)
+            .with(ORDER_QTY, 1);
+        assert_eq!(
+            fix.decode(&ob, &market).err(),
+            Some(FixError::BadField(ORD_TYPE))
+        );
+        let unsupported = FixMessage::new("H").with(SYMBOL, "ACME");
+        assert_eq!(
+            fix.decode(&ob, &unsupported).err(),
+            Some(FixError::UnsupportedMsgType(String::from("H")))
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_replace() {
+        let mut fix = Translator::new(TestCodec, "ACME");
+        let mut ob = OrderBook::default();
+        send(&mut fix, &mut ob, order("D", "s1", None, 2, 100, 10));
+        send(&mut fix, &mut ob, order("D", "b1", None, 1, 100, 4));
+
+        // s1 has 4 of 10 executed, so replacing it with 8 leaves 4
+        let reports = send(&mut fix, &mut ob, order("G", "s1.1", Some("s1"), 2, 102, 8));
+        assert_eq!(
+            fields(
+                &reports[0],
+                &[
+                    CL_ORD_ID,
+                    ORIG_CL_ORD_ID,
+                    EXEC_TYPE,
+                    ORD_STATUS,
+                    CUM_QTY,
+                    LEAVES_QTY
+                ]
+            ),
+            ["8", "s1.1", "s1", "5", "1", "4", "4"]
+        );
+        assert!(ob.order(&String::from("s1")).is_none());
+        assert_eq!(ob.best_ask(), Some((102, 4)));
+
+        // Executions carry over to the replacing ClOrdID
+        let reports = send(&mut fix, &mut ob, order("D", "b2", None, 1, 102, 1));
+        assert_eq!(
+            fields(&reports[2], SUMMARY),
+            ["8", "s1.1", "F", "1", "2", "1", "102", "5", "3"]
+        );
+
+        // Replacing with no more than was executed just cancels
+        send(&mut fix, &mut ob, order("D", "s3", None, 2, 110, 5));
+        let reports = send(
+            &mut fix,
+            &mut ob,
+            order("G", "s1.2", Some("s1.1"), 2, 102, 5),
+        );
+        assert_eq!(
+            fields(&reports[0], &[EXEC_TYPE, ORD_STATUS, CUM_QTY, LEAVES_QTY]),
+            ["8", "5", "2", "5", "0"]
+        );
+        assert_eq!(ob.best_ask(), Some((110, 5)));
+
+        // A replace to a ClOrdID that is resting leaves the original alone
+        send(&mut fix, &mut ob, order("D", "s4", None, 2, 111, 5));
+        let reports = send(&mut fix, &mut ob, order("G", "s3", Some("s4"), 2, 112, 5));
+        assert_eq!(
+            fields(
+                &reports[0],
+                &[ORDER_ID, ORD_STATUS, CXL_REJ_RESPONSE_TO, CXL_REJ_REASON]
+            ),
+            ["9", "s4", "0", "2", "6"]
+        );
+        assert!(ob.order(&String::from("s4")).is_some());
+
+        let cancel = FixMessage::new("F")
+            .with(CL_ORD_ID, "c1")
+            .with(ORIG_CL_ORD_ID, "s4")
+            .with(SYMBOL, "ACME")
+            .with(SIDE, 2);
+        let reports = send(&mut fix, &mut ob, cancel.clone());
+        assert_eq!(
+            fields(
+                &reports[0],
+                &[CL_ORD_ID, ORIG_CL_ORD_ID, EXEC_TYPE, ORD_STATUS, LEAVES_QTY]
+            ),
+            ["8", "c1", "s4", "4", "4", "0"]
+        );
+        let reports = send(&mut fix, &mut ob, cancel);
+        assert_eq!(
+            fields(
+                &reports[0],
+                &[ORDER_ID, ORD_STATUS, CXL_REJ_RESPONSE_TO, CXL_REJ_REASON]
+            ),
+            ["9", "NONE", "8", "1", "1"]
+        );
+    }
+}
@@ -0,0 +1,644 @@
+use crate::{
+    hash::FxHashMap,
+    ob::{ApplyError, Instruction, OrderBook},
+    order::OrderInterface,
+};
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Nasdaq TotalView-ITCH 5.0
+//
+// Files are a sequence of messages, each prefixed by its length as a big-endian
+// u16. Integers are big-endian, timestamps are nanoseconds since midnight in six
+// bytes and prices carry four implied decimal places, which are kept as-is.
+// Message types that do not affect the book are returned as `Other`.
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A resting order from an ITCH feed, identified by its order reference number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItchOrder {
+    reference: u64,
+    is_buy: bool,
+    price: u64,
+    quantity: u64,
+    remaining: u64,
+}
+
+impl ItchOrder {
+    #[inline]
+    pub fn new(reference: u64, is_buy: bool, price: u64, quantity: u64) -> Self {
+        Self {
+            reference,
+            is_buy,
+            price,
+            quantity,
+            remaining: quantity,
+        }
+    }
+}
+
+impl OrderInterface for ItchOrder {
+    type T = u64;
+    type N = u64;
+
+    #[inline(always)]
+    fn id(&self) -> &u64 {
+        &self.reference
+    }
+
+    #[inline(always)]
+    fn is_buy(&self) -> bool {
+        self.is_buy
+    }
+
+    #[inline(always)]
+    fn price(&self) -> u64 {
+        self.price
+    }
+
+    #[inline(always)]
+    fn quantity(&self) -> u64 {
+        self.quantity
+    }
+
+    #[inline(always)]
+    fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    #[inline(always)]
+    fn fill(&mut self, quantity: u64) {
+        self.remaining -= quantity;
+    }
+}
+
+/// An ITCH 5.0 message. Every variant but `Other` carries the stock locate code
+/// and the timestamp in nanoseconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItchMessage {
+    /// `R`: names the stock behind a locate code.
+    StockDirectory {
+        locate: u16,
+        timestamp: u64,
+        stock: [u8; 8],
+    },
+    /// `A`, or `F` when attributed to the market participant `mpid`.
+    AddOrder {
+        locate: u16,
+        timestamp: u64,
+        reference: u64,
+        is_buy: bool,
+        shares: u32,
+        stock: [u8; 8],
+        price: u32,
+        mpid: Option<[u8; 4]>,
+    },
+    /// `E`: executed at the order's price.
+    OrderExecuted {
+        locate: u16,
+        timestamp: u64,
+        reference: u64,
+        shares: u32,
+        match_number: u64,
+    },
+    /// `C`: executed at `price`, which can differ from the order's.
+    OrderExecutedWithPrice {
+        locate: u16,
+        timestamp: u64,
+        reference: u64,
+        shares: u32,
+        match_number: u64,
+        printable: bool,
+        price: u32,
+    },
+    /// `X`: part of the order was cancelled.
+    OrderCancel {
+        locate: u16,
+        timestamp: u64,
+        reference: u64,
+        shares: u32,
+    },
+    /// `D`: the rest of the order was cancelled.
+    OrderDelete {
+        locate: u16,
+        timestamp: u64,
+        reference: u64,
+    },
+    /// `U`: `original` is replaced by `reference` on the same side, losing priority.
+    OrderReplace {
+        locate: u16,
+        timestamp: u64,
+        original: u64,
+        reference: u64,
+        shares: u32,
+        price: u32,
+    },
+    /// Any other message type.
+    Other(u8),
+}
+
+impl ItchMessage {
+    /// Parses one message, without its length prefix.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ItchError> {
+        let Some(&kind) = bytes.first() else {
+            return Err(ItchError::BadMessage { kind: 0 });
+        };
+        let len = match kind {
+            b'R' => 39,
+            b'A' => 36,
+            b'F' => 40,
+            b'E' => 31,
+            b'C' => 36,
+            b'X' => 23,
+            b'D' => 19,
+            b'U' => 35,
+            _ => return Ok(ItchMessage::Other(kind)),
+        };
+        if bytes.len() < len {
+            return Err(ItchError::BadMessage { kind });
+        }
+        let int = |at: usize, n: usize| {
+            bytes[at..at + n]
+                .iter()
+                .fold(0u64, |acc, &b| acc << 8 | b as u64)
+        };
+        let locate = int(1, 2) as u16;
+        let timestamp = int(5, 6);
+        let stock = |at: usize| bytes[at..at + 8].try_into().unwrap();
+        let message = match kind {
+            b'R' => ItchMessage::StockDirectory {
+                locate,
+                timestamp,
+                stock: stock(11),
+            },
+            b'A' | b'F' => ItchMessage::AddOrder {
+                locate,
+                timestamp,
+                reference: int(11, 8),
+                is_buy: match bytes[19] {
+                    b'B' => true,
+                    b'S' => false,
+                    _ => return Err(ItchError::BadMessage { kind }),
+                },
+                shares: int(20, 4) as u32,
+                stock: stock(24),
+                price: int(32, 4) as u32,
+                mpid: (kind == b'F').then(|| bytes[36..40].try_into().unwrap()),
+            },
+            b'E' => ItchMessage::OrderExecuted {
+                locate,
+                timestamp,
+                reference: int(11, 8),
+                shares: int(19, 4) as u32,
+                match_number: int(23, 8),
+            },
+            b'C' => ItchMessage::OrderExecutedWithPrice {
+                locate,
+                timestamp,
+                reference: int(11, 8),
+                shares: int(19, 4) as u32,
+                match_number: int(23, 8),
+                printable: bytes[31] == b'Y',
+                price: int(32, 4) as u32,
+            },
+            b'X' => ItchMessage::OrderCancel {
+                locate,
+                timestamp,
+                reference: int(11, 8),
+                shares: int(19, 4) as u32,
+            },
+            b'D' => ItchMessage::OrderDelete {
+                locate,
+                timestamp,
+                reference: int(11, 8),
+            },
+            _ => ItchMessage::OrderReplace {
+                locate,
+                timestamp,
+                original: int(11, 8),
+                reference: int(19, 8),
+                shares: int(27, 4) as u32,
+                price: int(31, 4) as u32,
+            },
+        };
+        // An order resting zero shares is malformed, not an empty order
+        if let ItchMessage::AddOrder { shares: 0, .. }
+        | ItchMessage::OrderReplace { shares: 0, .. } = message
+        {
+            return Err(ItchError::BadMessage { kind });
+        }
+        Ok(message)
+    }
+}
+
+#[derive(Debug)]
+pub enum ItchError {
+    Io(io::Error),
+    /// The input ends partway through a message.
+    Truncated,
+    /// A message is too short for its type or has an invalid field, such as an
+    /// add or replace of zero shares.
+    BadMessage {
+        kind: u8,
+    },
+    /// The book for `locate` rejected a message, e.g. an execution of an unknown order.
+    Rejected {
+        locate: u16,
+        error: ApplyError<ItchOrder>,
+    },
+}
+
+impl fmt::Display for ItchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItchError::Io(e) => write!(f, "itch i/o error: {e}"),
+            ItchError::Truncated => write!(f, "itch input is truncated"),
+            ItchError::BadMessage { kind } => {
+                write!(f, "malformed itch message of type {:?}", *kind as char)
+            }
+            ItchError::Rejected { locate, error } => {
+                write!(f, "book for locate {locate} rejected message: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ItchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ItchError::Io(e) => Some(e),
+            ItchError::Rejected { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ItchError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => ItchError::Truncated,
+            _ => ItchError::Io(e),
+        }
+    }
+}
+
+/// Reads length-prefixed ITCH messages, e.g. from a buffered file.
+pub struct ItchReader<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> ItchReader<R> {
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Reads the next message, or None at a clean end of input.
+    pub fn next_message(&mut self) -> Result<Option<ItchMessage>, ItchError> {
+        let mut len = [0; 2];
+        match self.reader.read_exact(&mut len[..1]) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        self.reader.read_exact(&mut len[1..])?;
+        self.buf.resize(u16::from_be_bytes(len) as usize, 0);
+        self.reader.read_exact(&mut self.buf)?;
+        ItchMessage::parse(&self.buf).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for ItchReader<R> {
+    type Item = Result<ItchMessage, ItchError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message().transpose()
+    }
+}
+
+/// Builds one book per stock locate code by applying ITCH messages. Executions
+/// are applied at the resting order's price, wherever it is in the book.
+#[derive(Default)]
+pub struct ItchReplay {
+    books: FxHashMap<u16, OrderBook<ItchOrder>>,
+    stocks: FxHashMap<u16, [u8; 8]>,
+    messages: u64,
+}
+
+impl ItchReplay {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the book for a stock locate code, if any message referred to it.
+    #[inline]
+    pub fn book(&self, locate: u16) -> Option<&OrderBook<ItchOrder>> {
+        self.books.get(&locate)
+    }
+
+    /// Returns every book with its locate code, in no particular order.
+    #[inline]
+    pub fn books(&self) -> impl Iterator<Item = (u16, &OrderBook<ItchOrder>)> {
+        self.books.iter().map(|(&locate, ob)| (locate, ob))
+    }
+
+    /// Returns the symbol for a locate code, without padding.
+    #[inline]
+    pub fn stock(&self, locate: u16) -> Option<&str> {
+        let stock = self.stocks.get(&locate)?;
+        std::str::from_utf8(stock).ok().map(str::trim_end)
+    }
+
+    /// Returns the locate code for a symbol.
+    #[inline]
+    pub fn locate(&self, stock: &str) -> Option<u16> {
+        self.stocks
+            .keys()
+            .copied()
+            .find(|&locate| self.stock(locate) == Some(stock))
+    }
+
+    /// Returns the number of messages processed.
+    #[inline]
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Applies every message from `reader` and returns how many were read.
+    pub fn replay<R: Read>(&mut self, reader: R) -> Result<u64, ItchError> {
+        let start = self.messages;
+        for message in ItchReader::new(reader) {
+            self.process(&message?)?;
+        }
+        Ok(self.messages - start)
+    }
+
+    /// Applies one message to its stock's book.
+    pub fn process(&mut self, message: &ItchMessage) -> Result<(), ItchError> {
+        self.messages += 1;
+        let (locate, instructions) = match *message {
+            ItchMessage::StockDirectory { locate, stock, .. } => {
+                self.stocks.insert(locate, stock);
+                return Ok(());
+            }
+            ItchMessage::AddOrder {
+                locate,
+                reference,
+                is_buy,
+                shares,
+                stock,
+                price,
+                ..
+            } => {
+                self.stocks.entry(locate).or_insert(stock);
+                let order = ItchOrder::new(reference, is_buy, price as u64, shares as u64);
+                (locate, vec![Instruction::Insert(order, shares as u64)])
+            }
+            ItchMessage::OrderExecuted {
+                locate,
+                reference,
+                shares,
+                ..
+            }
+            | ItchMessage::OrderExecutedWithPrice {
+                locate,
+                reference,
+                shares,
+                ..
+            } => (locate, vec![Instruction::Fill(reference, shares as u64)]),
+            ItchMessage::OrderCancel {
+                locate,
+                reference,
+                shares,
+                ..
+            } => (locate, vec![Instruction::Reduce(reference, shares as u64)]),
+            ItchMessage::OrderDelete {
+                locate, reference, ..
+            } => (locate, vec![Instruction::Delete(reference)]),
+            ItchMessage::OrderReplace {
+                locate,
+                original,
+                reference,
+                shares,
+                price,
+                ..
+            } => {
+                let book = self.books.entry(locate).or_default();
+                let Some(is_buy) = book.order(&original).map(ItchOrder::is_buy) else {
+                    let error = ApplyError::UnknownOrder {
+                        index: 0,
+                        id: original,
+                    };
+                    return Err(ItchError::Rejected { locate, error });
+                };
+                let order = ItchOrder::new(reference, is_buy, price as u64, shares as u64);
+                // All-or-nothing, so a rejected insert leaves the original resting
+                return book
+                    .apply_atomic(vec![
+                        Instruction::Delete(original),
+                        Instruction::Insert(order, shares as u64),
+                    ])
+                    .map_err(|error| ItchError::Rejected { locate, error });
+            }
+            ItchMessage::Other(_) => return Ok(()),
+        };
+        self.books
+            .entry(locate)
+            .or_default()
+            .apply(instructions)
+            .map_err(|error| ItchError::Rejected { locate, error })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two stocks: AAPL (locate 1) and MSFT (locate 2).
+    ///
+    ///   S  system event
+    ///   R  1 AAPL, R 2 MSFT
+    ///   A  1 #1 buy 100 @ 150.0000
+    ///   A  1 #2 buy 200 @ 149.9000
+    ///   F  1 #3 sell 150 @ 150.1000 GSCO
+    ///   A  1 #4 sell 50 @ 150.1000
+    ///   E  1 #3 executed 100
+    ///   X  1 #2 cancelled 50
+    ///   U  1 #1 replaced by #5, 120 @ 150.0500
+    ///   C  1 #3 executed 50 @ 150.0800, printable
+    ///   A  2 #6 sell 300 @ 300.0000
+    ///   D  2 #6
+    ///   A  2 #7 buy 10 @ 299.0000
+    const SAMPLE: &[u8] = include_bytes!("../testdata/sample.itch");
+
+    #[test]
+    fn test_parse_sample() {
+        let messages: Vec<_> = ItchReader::new(SAMPLE).collect::<Result<_, _>>().unwrap();
+        assert_eq!(messages.len(), 14);
+        assert_eq!(messages[0], ItchMessage::Other(b'S'));
+        assert_eq!(
+            messages[5],
+            ItchMessage::AddOrder {
+                locate: 1,
+                timestamp: 34_200_000_006_000,
+                reference: 3,
+                is_buy: false,
+                shares: 150,
+                stock: *b"AAPL    ",
+                price: 1_501_000,
+                mpid: Some(*b"GSCO"),
+            }
+        );
+        assert_eq!(
+            messages[10],
+            ItchMessage::OrderExecutedWithPrice {
+                locate: 1,
+                timestamp: 34_200_000_011_000,
+                reference: 3,
+                shares: 50,
+                match_number: 2,
+                printable: true,
+                price: 1_500_800,
+            }
+        );
+    }
+
+    #[test]
+    fn test_replay_sample() {
+        let mut replay = ItchReplay::new();
+        assert_eq!(replay.replay(SAMPLE).unwrap(), 14);
+        assert_eq!(replay.locate("AAPL"), Some(1));
+        assert_eq!(replay.stock(2), Some("MSFT"));
+
+        let aapl = replay.book(1).unwrap();
+        assert_eq!(aapl.top_bids(5), vec![(1_500_500, 120), (1_499_000, 150)]);
+        assert_eq!(aapl.top_asks(5), vec![(1_501_000, 50)]);
+        assert!(aapl.order(&1).is_none());
+        assert!(aapl.order(&3).is_none());
+        // The partial cancel left the rest resting
+        assert_eq!(aapl.order(&2).unwrap().remaining(), 150);
+
+        let msft = replay.book(2).unwrap();
+        assert_eq!(msft.best_bid(), Some((2_990_000, 10)));
+        assert!(msft.best_ask().is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut replay = ItchReplay::new();
+        assert!(matches!(
+            replay.replay(&SAMPLE[..SAMPLE.len() - 3]),
+            Err(ItchError::Truncated)
+        ));
+
+        assert!(matches!(
+            ItchMessage::parse(&[b'D', 0, 1]),
+            Err(ItchError::BadMessage { kind: b'D' })
+        ));
+
+        let mut replay = ItchReplay::new();
+        let err = replay.process(&ItchMessage::OrderDelete {
+            locate: 3,
+            timestamp: 0,
+            reference: 9,
+        });
+        assert!(matches!(
+            err,
+            Err(ItchError::Rejected {
+                locate: 3,
+                error: ApplyError::UnknownOrder { id: 9, .. }
+            })
+        ));
+
+        // Replacing with a reference that is already resting changes nothing
+        for reference in [1, 2] {
+            replay
+                .process(&ItchMessage::AddOrder {
+                    locate: 3,
+                    timestamp: 0,
+                    reference,
+                    is_buy: true,
+                    shares: 100,
+                    stock: *b"AAPL    ",
+                    price: 1_500_000,
+                    mpid: None,
+                })
+                .unwrap();
+        }
+        let err = replay.process(&ItchMessage::OrderReplace {
+            locate: 3,
+            timestamp: 0,
+            original: 1,
+            reference: 2,
+            shares: 50,
+            price: 1_500_100,
+        });
+        assert!(matches!(
+            err,
+            Err(ItchError::Rejected {
+                locate: 3,
+                error: ApplyError::DuplicateOrder { index: 1, id: 2 }
+            })
+        ));
+        assert_eq!(replay.book(3).unwrap().len(), 2);
+        assert!(replay.book(3).unwrap().order(&1).is_some());
+
+        // Zero-share adds and replaces fail to parse, and the book rejects them
+        // if built by hand, leaving the original of a replace resting
+        let mut add = [0; 36];
+        add[0] = b'A';
+        add[19] = b'B';
+        assert!(matches!(
+            ItchMessage::parse(&add),
+            Err(ItchError::BadMessage { kind: b'A' })
+        ));
+        let mut replace = [0; 35];
+        replace[0] = b'U';
+        assert!(matches!(
+            ItchMessage::parse(&replace),
+            Err(ItchError::BadMessage { kind: b'U' })
+        ));
+        let err = replay.process(&ItchMessage::AddOrder {
+            locate: 3,
+            timestamp: 0,
+            reference: 3,
+            is_buy: true,
+            shares: 0,
+            stock: *b"AAPL    ",
+            price: 1_500_000,
+            mpid: None,
+        });
+        assert!(matches!(
+            err,
+            Err(ItchError::Rejected {
+                locate: 3,
+                error: ApplyError::ZeroQuantity { index: 0, id: 3 }
+            })
+        ));
+        let err = replay.process(&ItchMessage::OrderReplace {
+            locate: 3,
+            timestamp: 0,
+            original: 1,
+            reference: 3,
+            shares: 0,
+            price: 1_500_100,
+        });
+        assert!(matches!(
+            err,
+            Err(ItchError::Rejected {
+                locate: 3,
+                error: ApplyError::ZeroQuantity { index: 1, id: 3 }
+            })
+        ));
+        assert_eq!(replay.book(3).unwrap().order(&1).unwrap().remaining(), 100);
+        assert!(replay.book(3).unwrap().order(&3).is_none());
+    }
+}
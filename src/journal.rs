@@ -0,0 +1,646 @@
+use crate::{
+    checksum::Crc32,
+    ob::{ApplyError, Instruction, Msg, OrderBook},
+    order::OrderInterface,
+    snapshot::OrderCodec,
+};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Journal format (little-endian)
+//
+//   magic "OBJL" | version u16
+//   then records: length u32 | kind u8 | sequence u64 | payload | crc32 u32
+//
+// `length` counts kind through payload, and the crc covers the same bytes.
+//   batch payload:      instruction count u32, then per instruction a tag u8
+//                       and its fields, each framed as length u32 | codec bytes
+//   checkpoint payload: state crc32 u32 after the batch with this sequence
+//
+// The state crc covers every resting order, bids then asks, each in priority
+// order, as side u8 (1 = bid) then order, price and remaining quantity framed
+// like batch fields, so it depends only on the codec and not on the platform.
+//
+// Batch sequences start at 1 and increase by one.
+// ─────────────────────────────────────────────────────────────────────────────
+
+const MAGIC: &[u8; 4] = b"OBJL";
+const VERSION: u16 = 1;
+
+const BATCH: u8 = 0;
+const CHECKPOINT: u8 = 1;
+
+const INSERT: u8 = 0;
+const DELETE: u8 = 1;
+const FILL: u8 = 2;
+const NOOP: u8 = 3;
+const REDUCE: u8 = 4;
+
+/// Encodes the instruction fields that are not whole orders.
+pub trait InstructionCodec<O: OrderInterface>: OrderCodec<O> {
+    fn encode_id(&self, id: &O::T, out: &mut Vec<u8>);
+
+    /// Decodes an id from exactly the bytes `encode_id` produced, or None if invalid.
+    fn decode_id(&self, bytes: &[u8]) -> Option<O::T>;
+
+    fn encode_quantity(&self, quantity: O::N, out: &mut Vec<u8>);
+
+    /// Decodes a quantity from exactly the bytes `encode_quantity` produced.
+    fn decode_quantity(&self, bytes: &[u8]) -> Option<O::N>;
+}
+
+/// Why a replay stopped. `sequence` is the last batch applied before the failure.
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// The journal ends partway through a record, e.g. after a crash mid-write.
+    Truncated {
+        sequence: u64,
+    },
+    /// A record failed its checksum or could not be decoded.
+    Corrupt {
+        sequence: u64,
+    },
+    /// A record's sequence does not follow the previous batch.
+    OutOfSequence {
+        expected: u64,
+        found: u64,
+    },
+    /// The book rejected the instruction at `index` of the next batch, which was
+    /// not applied. Usually means replay started from the wrong book.
+    Rejected {
+        sequence: u64,
+        index: usize,
+    },
+    /// The book state after a batch differs from the checkpoint recorded for it.
+    HashMismatch {
+        sequence: u64,
+    },
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(e) => write!(f, "journal i/o error: {e}"),
+            JournalError::BadMagic => write!(f, "not an orderbook journal"),
+            JournalError::UnsupportedVersion(v) => write!(f, "unsupported journal version {v}"),
+            JournalError::Truncated { sequence } => {
+                write!(f, "journal is truncated after batch {sequence}")
+            }
+            JournalError::Corrupt { sequence } => {
+                write!(f, "journal record after batch {sequence} is corrupt")
+            }
+            JournalError::OutOfSequence { expected, found } => {
+                write!(f, "expected journal batch {expected}, found {found}")
+            }
+            JournalError::Rejected { sequence, index } => write!(
+                f,
+                "journal batch after {sequence} has invalid instruction {index}"
+            ),
+            JournalError::HashMismatch { sequence } => {
+                write!(f, "book state mismatch after journal batch {sequence}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for JournalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JournalError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JournalError {
+    fn from(e: io::Error) -> Self {
+        JournalError::Io(e)
+    }
+}
+
+/// Appends sequenced instruction batches to a writer, typically a file opened
+/// for appending. `commit` journals each batch from `eval` before applying it.
+/// Every record is written with a single `write_all`; `flush` and sync the
+/// underlying file for durability.
+pub struct Journal<W: Write> {
+    writer: W,
+    sequence: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Journal<W> {
+    /// Starts a new journal, writing its header.
+    #[inline]
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self::resume(writer, 0))
+    }
+
+    /// Continues an existing journal whose last batch was `sequence`, e.g. as
+    /// returned by `OrderBook::replay`.
+    #[inline]
+    pub fn resume(writer: W, sequence: u64) -> Self {
+        Self {
+            writer,
+            sequence,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Returns the sequence of the last batch appended.
+    #[inline]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Validates a batch against `ob`, appends it, then applies it, and returns
+    /// its sequence. A batch the book rejects is neither journaled nor applied,
+    /// so replay never meets it.
+    pub fn commit<O: OrderInterface, C: InstructionCodec<O>>(
+        &mut self,
+        ob: &mut OrderBook<O>,
+        codec: &C,
+        instructions: Vec<Instruction<O>>,
+    ) -> Result<u64, JournalError> {
+        let last = self.sequence;
+        let rejected = |e: ApplyError<O>| JournalError::Rejected {
+            sequence: last,
+            index: e.index(),
+        };
+        ob.validate(&instructions).map_err(rejected)?;
+        let sequence = self.append(codec, &instructions)?;
+        ob.apply(instructions).map_err(rejected)?;
+        Ok(sequence)
+    }
+
+    /// Appends a batch without checking it and returns its sequence. Prefer
+    /// `commit` unless the batch is already known to apply.
+    pub fn append<O: OrderInterface, C: InstructionCodec<O>>(
+        &mut self,
+        codec: &C,
+        instructions: &[Instruction<O>],
+    ) -> io::Result<u64> {
+        let sequence = self.sequence + 1;
+        self.record(BATCH, sequence, |out| {
+            out.extend_from_slice(&(instructions.len() as u32).to_le_bytes());
+            for instruction in instructions {
+                match instruction {
+                    Instruction::Insert(order, quantity) => {
+                        out.push(INSERT);
+                        field(out, |out| codec.encode(order, out));
+                        field(out, |out| codec.encode_quantity(*quantity, out));
+                    }
+                    Instruction::Delete(id) => {
+                        out.push(DELETE);
+                        field(out, |out| codec.encode_id(id, out));
+                    }
+                    Instruction::Fill(id, quantity) => {
+                        out.push(FILL);
+                        field(out, |out| codec.encode_id(id, out));
+                        field(out, |out| codec.encode_quantity(*quantity, out));
+                    }
+                    Instruction::Reduce(id, quantity) => {
+                        out.push(REDUCE);
+                        field(out, |out| codec.encode_id(id, out));
+                        field(out, |out| codec.encode_quantity(*quantity, out));
+                    }
+                    Instruction::NoOp(msg) => {
+                        out.push(NOOP);
+                        out.push(match msg {
+                            Msg::OrderNotFound => 0,
+                            Msg::OrderAlreadyExists => 1,
+                        });
+                    }
+                }
+            }
+        })?;
+        self.sequence = sequence;
+        Ok(sequence)
+    }
+
+    /// Records a checksum of the book's state after the last appended batch, so
+    /// replay can verify it reached the same state. Walks every resting order.
+    pub fn checkpoint<O: OrderInterface, C: InstructionCodec<O>>(
+        &mut self,
+        ob: &OrderBook<O>,
+        codec: &C,
+    ) -> io::Result<()> {
+        let crc = state_crc(ob, codec, &mut Vec::new());
+        self.record(CHECKPOINT, self.sequence, |out| {
+            out.extend_from_slice(&crc.to_le_bytes())
+        })
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn record(
+        &mut self,
+        kind: u8,
+        sequence: u64,
+        payload: impl FnOnce(&mut Vec<u8>),
+    ) -> io::Result<()> {
+        let out = &mut self.buf;
+        out.clear();
+        out.extend_from_slice(&[0; 4]);
+        out.push(kind);
+        out.extend_from_slice(&sequence.to_le_bytes());
+        payload(out);
+        let len = (out.len() - 4) as u32;
+        out[..4].copy_from_slice(&len.to_le_bytes());
+        let crc = Crc32::checksum(&out[4..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+        self.writer.write_all(out)
+    }
+}
+
+impl<O: OrderInterface> OrderBook<O> {
+    /// Applies every batch in a journal to this book, in order, and returns the
+    /// last batch's sequence. Batches are applied atomically and checkpoints are
+    /// verified against the book's state. On error,
+    /// every batch up to the one named in the error has been applied.
+    pub fn replay<R: Read, C: InstructionCodec<O>>(
+        &mut self,
+        mut reader: R,
+        codec: &C,
+    ) -> Result<u64, JournalError> {
+        let mut header = [0; 6];
+        if read_full(&mut reader, &mut header)? < header.len() {
+            return Err(JournalError::Truncated { sequence: 0 });
+        }
+        if &header[..4] != MAGIC {
+            return Err(JournalError::BadMagic);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(JournalError::UnsupportedVersion(version));
+        }
+        let mut sequence = 0;
+        let mut buf = Vec::new();
+        let mut scratch = Vec::new();
+        let mut instructions = Vec::new();
+        while read_record(&mut reader, &mut buf, sequence)? {
+            let corrupt = JournalError::Corrupt { sequence };
+            let kind = buf[0];
+            let found = u64::from_le_bytes(buf[1..9].try_into().unwrap());
+            let mut payload = &buf[9..buf.len() - 4];
+            match kind {
+                BATCH => {
+                    if found != sequence + 1 {
+                        return Err(JournalError::OutOfSequence {
+                            expected: sequence + 1,
+                            found,
+                        });
+                    }
+                    decode_batch(&mut payload, codec, &mut instructions).ok_or(corrupt)?;
+                    self.apply_atomic(std::mem::take(&mut instructions))
+                        .map_err(|e| JournalError::Rejected {
+                            sequence,
+                            index: e.index(),
+                        })?;
+                    sequence = found;
+                }
+                CHECKPOINT => {
+                    if found != sequence {
+                        return Err(JournalError::OutOfSequence {
+                            expected: sequence,
+                            found,
+                        });
+                    }
+                    let crc = u32::from_le_bytes(
+                        take(&mut payload, 4).ok_or(corrupt)?.try_into().unwrap(),
+                    );
+                    if state_crc(self, codec, &mut scratch) != crc {
+                        return Err(JournalError::HashMismatch { sequence });
+                    }
+                }
+                _ => return Err(corrupt),
+            }
+        }
+        Ok(sequence)
+    }
+}
+
+/// CRC-32 of the book's resting orders in the encoding described at the top of
+/// this file, using `buf` as scratch space.
+fn state_crc<O: OrderInterface, C: InstructionCodec<O>>(
+    ob: &OrderBook<O>,
+    codec: &C,
+    buf: &mut Vec<u8>,
+) -> u32 {
+    let mut crc = Crc32::new();
+    for entry in ob.bid_orders().chain(ob.ask_orders()) {
+        buf.clear();
+        buf.push(entry.order.is_buy() as u8);
+        field(buf, |out| codec.encode(entry.order, out));
+        field(buf, |out| codec.encode_quantity(entry.price, out));
+        field(buf, |out| {
+            codec.encode_quantity(entry.order.remaining(), out)
+        });
+        crc.update(buf);
+    }
+    crc.finish()
+}
+
+/// Appends one field framed as `length u32 | bytes`.
+#[inline(always)]
+fn field(out: &mut Vec<u8>, encode: impl FnOnce(&mut Vec<u8>)) {
+    let len_at = out.len();
+    out.extend_from_slice(&[0; 4]);
+    encode(out);
+    let len = (out.len() - len_at - 4) as u32;
+    out[len_at..len_at + 4].copy_from_slice(&len.to_le_bytes());
+}
+
+/// Reads the next record into `buf` (kind through crc), verifying its checksum.
+/// Returns false at a clean end of the journal.
+fn read_record<R: Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    sequence: u64,
+) -> Result<bool, JournalError> {
+    let mut len = [0; 4];
+    match read_full(reader, &mut len)? {
+        0 => return Ok(false),
+        4 => {}
+        _ => return Err(JournalError::Truncated { sequence }),
+    }
+    let len = u32::from_le_bytes(len) as u64 + 4;
+    buf.clear();
+    reader.by_ref().take(len).read_to_end(buf)?;
+    if (buf.len() as u64) < len {
+        return Err(JournalError::Truncated { sequence });
+    }
+    let (body, crc) = buf.split_last_chunk::<4>().unwrap();
+    if body.len() < 9 || Crc32::checksum(body) != u32::from_le_bytes(*crc) {
+        return Err(JournalError::Corrupt { sequence });
+    }
+    Ok(true)
+}
+
+fn decode_batch<O: OrderInterface, C: InstructionCodec<O>>(
+    payload: &mut &[u8],
+    codec: &C,
+    out: &mut Vec<Instruction<O>>,
+) -> Option<()> {
+    let count = u32::from_le_bytes(take(payload, 4)?.try_into().ok()?);
+    for _ in 0..count {
+        let instruction = match take(payload, 1)?[0] {
+            INSERT => Instruction::Insert(
+                codec.decode(read_field(payload)?)?,
+                codec.decode_quantity(read_field(payload)?)?,
+            ),
+            DELETE => Instruction::Delete(codec.decode_id(read_field(payload)?)?),
+            FILL => Instruction::Fill(
+                codec.decode_id(read_field(payload)?)?,
+                codec.decode_quantity(read_field(payload)?)?,
+            ),
+            REDUCE => Instruction::Reduce(
+                codec.decode_id(read_field(payload)?)?,
+                codec.decode_quantity(read_field(payload)?)?,
+            ),
+            NOOP => Instruction::NoOp(match take(payload, 1)?[0] {
+                0 => Msg::OrderNotFound,
+                1 => Msg::OrderAlreadyExists,
+                _ => return None,
+            }),
+            _ => return None,
+        };
+        out.push(instruction);
+    }
+    payload.is_empty().then_some(())
+}
+
+#[inline(always)]
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    let (head, tail) = bytes.split_at_checked(n)?;
+    *bytes = tail;
+    Some(head)
+}
+
+#[inline(always)]
+fn read_field<'a>(bytes: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = u32::from_le_bytes(take(bytes, 4)?.try_into().ok()?);
+    take(bytes, len as usize)
+}
+
+/// Reads until `buf` is full or the reader is exhausted, returning bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Op,
+        order::{TestCodec, TestOrder},
+    };
+
+    /// Runs each batch of ops through eval and commits it.
+    fn journaled(batches: Vec<Vec<Op<TestOrder>>>) -> (OrderBook<TestOrder>, Vec<u8>) {
+        let mut ob = OrderBook::default();
+        let mut journal = Journal::new(Vec::new()).unwrap();
+        for ops in batches {
+            let (_, instructions) = ob.eval(ops);
+            journal.commit(&mut ob, &TestCodec, instructions).unwrap();
+        }
+        journal.checkpoint(&ob, &TestCodec).unwrap();
+        (ob, journal.into_inner())
+    }
+
+    fn batches() -> Vec<Vec<Op<TestOrder>>> {
+        vec![
+            vec![
+                Op::Insert(TestOrder::new("s1", false, 1000, 100)),
+                Op::Insert(TestOrder::new("b1", true, 990, 30)),
+            ],
+            vec![
+                Op::Insert(TestOrder::new("b2", true, 1000, 40)),
+                Op::Delete(String::from("x")),
+            ],
+            vec![
+                Op::Delete(String::from("b1")),
+                Op::Insert(TestOrder::new("s2", false, 1000, 10)),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_replay() {
+        let (ob, bytes) = journaled(batches());
+        let mut replayed = OrderBook::default();
+        assert_eq!(replayed.replay(bytes.as_slice(), &TestCodec).unwrap(), 3);
+        assert_eq!(replayed.l3_checksum(), ob.l3_checksum());
+        assert_eq!(replayed.compute_hash(), ob.compute_hash());
+        assert_eq!(replayed.order(&String::from("s1")).unwrap().remaining(), 60);
+
+        // Appending after replay continues the sequence
+        let mut journal = Journal::resume(bytes, 3);
+        assert_eq!(journal.append::<TestOrder, _>(&TestCodec, &[]).unwrap(), 4);
+        let bytes = journal.into_inner();
+        let mut again = OrderBook::default();
+        assert_eq!(again.replay(bytes.as_slice(), &TestCodec).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_commit_rejected() {
+        let (mut ob, bytes) = journaled(batches());
+        let mut journal = Journal::resume(bytes.clone(), 3);
+        let err = journal.commit(
+            &mut ob,
+            &TestCodec,
+            vec![
+                Instruction::Delete(String::from("s2")),
+                Instruction::Fill(String::from("s1"), 100),
+            ],
+        );
+        assert!(matches!(
+            err,
+            Err(JournalError::Rejected {
+                sequence: 3,
+                index: 1
+            })
+        ));
+        assert_eq!(journal.sequence(), 3);
+        assert_eq!(journal.get_ref(), &bytes);
+        assert!(ob.order(&String::from("s2")).is_some());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let (_, bytes) = journaled(batches());
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(&bytes[..bytes.len() - 1], &TestCodec),
+            Err(JournalError::Truncated { sequence: 3 })
+        ));
+        // Every complete batch was applied
+        assert_eq!(ob.len(), 2);
+
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(&bytes[..3], &TestCodec),
+            Err(JournalError::Truncated { sequence: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let (_, mut bytes) = journaled(batches());
+        // First byte of the first record's payload
+        bytes[6 + 4 + 9] ^= 0xff;
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(bytes.as_slice(), &TestCodec),
+            Err(JournalError::Corrupt { sequence: 0 })
+        ));
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            ob.replay(bytes.as_slice(), &TestCodec),
+            Err(JournalError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let mut journal = Journal::new(Vec::new()).unwrap();
+        journal
+            .append(
+                &TestCodec,
+                &[Instruction::Insert(
+                    TestOrder::new("s1", false, 1000, 10),
+                    10,
+                )],
+            )
+            .unwrap();
+        journal
+            .checkpoint(&OrderBook::<TestOrder>::default(), &TestCodec)
+            .unwrap();
+
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(journal.get_ref().as_slice(), &TestCodec),
+            Err(JournalError::HashMismatch { sequence: 1 })
+        ));
+
+        // The checkpoint covers queue order, not just the set of orders
+        let b1 = TestOrder::new("b1", true, 990, 10);
+        let b2 = TestOrder::new("b2", true, 990, 10);
+        let mut swapped = OrderBook::<TestOrder>::default();
+        swapped
+            .apply(vec![
+                Instruction::Insert(b2.clone(), 10),
+                Instruction::Insert(b1.clone(), 10),
+            ])
+            .unwrap();
+        let mut journal = Journal::new(Vec::new()).unwrap();
+        journal
+            .append(
+                &TestCodec,
+                &[Instruction::Insert(b1, 10), Instruction::Insert(b2, 10)],
+            )
+            .unwrap();
+        journal.checkpoint(&swapped, &TestCodec).unwrap();
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(journal.get_ref().as_slice(), &TestCodec),
+            Err(JournalError::HashMismatch { sequence: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_out_of_sequence() {
+        let mut journal = Journal::resume(Vec::new(), 0);
+        journal.get_mut().extend_from_slice(MAGIC);
+        journal.get_mut().extend_from_slice(&VERSION.to_le_bytes());
+        journal.append::<TestOrder, _>(&TestCodec, &[]).unwrap();
+        let mut bytes = journal.into_inner();
+        let mut skipped = Journal::resume(Vec::new(), 5);
+        skipped.append::<TestOrder, _>(&TestCodec, &[]).unwrap();
+        bytes.extend_from_slice(skipped.get_ref());
+
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(matches!(
+            ob.replay(bytes.as_slice(), &TestCodec),
+            Err(JournalError::OutOfSequence {
+                expected: 2,
+                found: 6
+            })
+        ));
+    }
+}
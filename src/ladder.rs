@@ -0,0 +1,248 @@
+use crate::{level::Level, order::OrderInterface};
+
+/// Levels for the prices `min`, `min + tick`, ... up to a fixed count, indexed by
+/// tick, with a bitmap of occupied ticks so finding the next level skips empty
+/// ticks 64 at a time.
+pub(crate) struct Ladder<O: OrderInterface> {
+    min: O::N,
+    tick: O::N,
+    levels: Vec<Option<Level<O>>>,
+    /// Bit `i % 64` of word `i / 64` is set if tick `i` has a level.
+    bits: Vec<u64>,
+    len: usize,
+    /// Converts a tick count to an index, or None if it does not fit.
+    to_index: fn(O::N) -> Option<usize>,
+}
+
+impl<O: OrderInterface> Ladder<O> {
+    /// Creates a ladder for prices `min..=max` in steps of `tick`.
+    /// Panics if `tick` is zero, `max < min` or the range does not fit in memory.
+    pub(crate) fn new(min: O::N, max: O::N, tick: O::N) -> Self
+    where
+        O::N: TryInto<usize>,
+    {
+        assert!(tick > O::N::default(), "ladder tick must be positive");
+        assert!(min <= max, "ladder min must not exceed max");
+        let to_index: fn(O::N) -> Option<usize> = |ticks| ticks.try_into().ok();
+        let len = to_index((max - min) / tick)
+            .and_then(|ticks| ticks.checked_add(1))
+            .expect("ladder range does not fit in memory");
+        Self {
+            min,
+            tick,
+            levels: (0..len).map(|_| None).collect(),
+            bits: vec![0; len.div_ceil(64)],
+            len: 0,
+            to_index,
+        }
+    }
+
+    /// Occupied levels.
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the index of `price`, or None if it is off the ladder.
+    #[inline(always)]
+    pub(crate) fn index(&self, price: O::N) -> Option<usize> {
+        if price < self.min {
+            return None;
+        }
+        let offset = price - self.min;
+        let ticks = offset / self.tick;
+        if ticks * self.tick != offset {
+            return None;
+        }
+        (self.to_index)(ticks).filter(|&i| i < self.levels.len())
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self, index: usize) -> Option<&Level<O>> {
+        self.levels[index].as_ref()
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut Level<O>> {
+        self.levels[index].as_mut()
+    }
+
+    /// Returns the level at `index`, creating it at `price` if there is none.
+    #[inline(always)]
+    pub(crate) fn entry(&mut self, index: usize, price: O::N) -> &mut Level<O> {
+        let slot = &mut self.levels[index];
+        if slot.is_none() {
+            self.bits[index / 64] |= 1 << (index % 64);
+            self.len += 1;
+        }
+        slot.get_or_insert_with(|| Level::new(price))
+    }
+
+    /// Iterates the occupied levels, in index order.
+    #[inline]
+    pub(crate) fn levels_mut(&mut self) -> impl Iterator<Item = &mut Level<O>> {
+        self.levels.iter_mut().flatten()
+    }
+
+    #[inline(always)]
+    pub(crate) fn remove(&mut self, index: usize) {
+        if self.levels[index].take().is_some() {
+            self.bits[index / 64] &= !(1 << (index % 64));
+            self.len -= 1;
+        }
+    }
+
+    /// Returns the best occupied index: highest for bids, lowest for asks.
+    #[inline(always)]
+    pub(crate) fn best(&self, is_bid: bool) -> Option<usize> {
+        if is_bid {
+            self.down(self.levels.len().checked_sub(1)?)
+        } else {
+            self.up(0)
+        }
+    }
+
+    /// Returns the next occupied index worse than `index`.
+    #[inline(always)]
+    pub(crate) fn after(&self, index: usize, is_bid: bool) -> Option<usize> {
+        if is_bid {
+            self.down(index.checked_sub(1)?)
+        } else {
+            self.up(index + 1)
+        }
+    }
+
+    /// Returns the best occupied index worse than `price`, which need not be on
+    /// the ladder.
+    #[inline]
+    pub(crate) fn after_price(&self, price: O::N, is_bid: bool) -> Option<usize> {
+        if price < self.min {
+            return if is_bid { None } else { self.up(0) };
+        }
+        let offset = price - self.min;
+        let ticks = offset / self.tick;
+        let index = (self.to_index)(ticks);
+        if is_bid {
+            let last = self.levels.len().checked_sub(1)?;
+            let from = match index {
+                Some(i) if ticks * self.tick == offset => i.checked_sub(1)?,
+                Some(i) => i,
+                None => last,
+            };
+            self.down(from.min(last))
+        } else {
+            self.up(index?.checked_add(1)?)
+        }
+    }
+
+    /// Lowest occupied index at or above `from`.
+    #[inline]
+    fn up(&self, from: usize) -> Option<usize> {
+        let mut word = from / 64;
+        let mut bits = self.bits.get(word)? & (!0 << (from % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + bits.trailing_zeros() as usize);
+            }
+            word += 1;
+            bits = *self.bits.get(word)?;
+        }
+    }
+
+    /// Highest occupied index at or below `from`, which must be on the ladder.
+    #[inline]
+    fn down(&self, from: usize) -> Option<usize> {
+        let mut word = from / 64;
+        let mut bits = self.bits[word] & (!0 >> (63 - from % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + 63 - bits.leading_zeros() as usize);
+            }
+            word = word.checked_sub(1)?;
+            bits = self.bits[word];
+        }
+    }
+}
+
+impl<O: OrderInterface> Clone for Ladder<O> {
+    fn clone(&self) -> Self {
+        Self {
+            min: self.min,
+            tick: self.tick,
+            levels: self.levels.clone(),
+            bits: self.bits.clone(),
+            len: self.len,
+            to_index: self.to_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::TestOrder;
+
+    #[test]
+    fn test_index() {
+        let ladder = Ladder::<TestOrder>::new(100, 200, 5);
+        assert_eq!(ladder.index(100), Some(0));
+        assert_eq!(ladder.index(135), Some(7));
+        assert_eq!(ladder.index(200), Some(20));
+        assert_eq!(ladder.index(99), None);
+        assert_eq!(ladder.index(101), None);
+        assert_eq!(ladder.index(205), None);
+    }
+
+    #[test]
+    fn test_search_across_words() {
+        let mut ladder = Ladder::<TestOrder>::new(0, 299, 1);
+        for price in [3, 64, 130, 299] {
+            let index = ladder.index(price).unwrap();
+            ladder.entry(index, price);
+        }
+        assert_eq!(ladder.len(), 4);
+
+        let walk = |ladder: &Ladder<TestOrder>, is_bid: bool| {
+            let mut out = Vec::new();
+            let mut next = ladder.best(is_bid);
+            while let Some(index) = next {
+                out.push(ladder.get(index).unwrap().price());
+                next = ladder.after(index, is_bid);
+            }
+            out
+        };
+        assert_eq!(walk(&ladder, false), [3, 64, 130, 299]);
+        assert_eq!(walk(&ladder, true), [299, 130, 64, 3]);
+
+        ladder.remove(64);
+        ladder.remove(64);
+        ladder.remove(299);
+        assert_eq!(ladder.len(), 2);
+        assert_eq!(walk(&ladder, false), [3, 130]);
+        assert_eq!(walk(&ladder, true), [130, 3]);
+    }
+
+    #[test]
+    fn test_after_price() {
+        let mut ladder = Ladder::<TestOrder>::new(100, 1000, 10);
+        for price in [100, 300, 990] {
+            let index = ladder.index(price).unwrap();
+            ladder.entry(index, price);
+        }
+        let after = |price: u64, is_bid: bool| {
+            ladder
+                .after_price(price, is_bid)
+                .map(|i| ladder.get(i).unwrap().price())
+        };
+        assert_eq!(after(300, false), Some(990));
+        assert_eq!(after(295, false), Some(300));
+        assert_eq!(after(50, false), Some(100));
+        assert_eq!(after(990, false), None);
+        assert_eq!(after(5000, false), None);
+        assert_eq!(after(300, true), Some(100));
+        assert_eq!(after(305, true), Some(300));
+        assert_eq!(after(5000, true), Some(990));
+        assert_eq!(after(100, true), None);
+        assert_eq!(after(50, true), None);
+    }
+}
@@ -1,5 +1,8 @@
+use std::ops::Deref;
+
 use crate::{
-    list::{Iter, IterMut, List, Node},
+    fenwick::Fenwick,
+    list::{Handle, Iter, List, Slab},
     order::OrderInterface,
 };
 
@@ -9,6 +12,25 @@ pub struct Level<O: OrderInterface> {
     orders: List<O>,
     /// Total quantity across all orders (cached for performance).
     total_quantity: O::N,
+    /// Prefix sums by list rank, kept once `track_queue` is called.
+    queue: Option<Queue<O::N>>,
+}
+
+/// Orders and quantity resting at each list rank, zero where an order left.
+#[derive(Clone)]
+struct Queue<N> {
+    counts: Fenwick<u32>,
+    quantities: Fenwick<N>,
+}
+
+impl<N: Copy + Default + std::ops::AddAssign + std::ops::SubAssign> Queue<N> {
+    /// Renumbers `orders` and rebuilds the sums from them, dropping the gaps.
+    fn rebuild<O: OrderInterface<N = N>>(&mut self, orders: &mut List<O>, nodes: &mut Slab<O>) {
+        orders.renumber(nodes);
+        self.counts.rebuild(orders.iter(nodes).map(|_| 1));
+        self.quantities
+            .rebuild(orders.iter(nodes).map(|order| order.remaining()));
+    }
 }
 
 impl<O: OrderInterface> Level<O> {
@@ -18,9 +40,27 @@ impl<O: OrderInterface> Level<O> {
             price,
             orders: List::new(),
             total_quantity: O::N::default(),
+            queue: None,
         }
     }
 
+    /// Starts keeping prefix sums over the level's FIFO order, so `queue_position`
+    /// takes O(log n) instead of walking the level. Adds, fills and removals then
+    /// cost O(log n) more each, with an occasional O(n) compaction.
+    pub(crate) fn track_queue(&mut self, nodes: &mut Slab<O>) {
+        let queue = self.queue.get_or_insert_with(|| Queue {
+            counts: Fenwick::new(),
+            quantities: Fenwick::new(),
+        });
+        queue.rebuild(&mut self.orders, nodes);
+    }
+
+    /// Returns true if `track_queue` was called.
+    #[inline]
+    pub fn tracks_queue(&self) -> bool {
+        self.queue.is_some()
+    }
+
     #[inline]
     pub fn price(&self) -> O::N {
         self.price
@@ -41,40 +81,178 @@ impl<O: OrderInterface> Level<O> {
         self.orders.is_empty()
     }
 
-    /// Adds an order to this level (FIFO). Returns pointer to the inserted node.
+    /// Adds an order to this level (FIFO), in a node from `nodes`. Returns a
+    /// handle to the inserted node.
     #[inline(always)]
-    pub fn add_order(&mut self, order: O) -> *mut Node<O> {
-        self.total_quantity += order.remaining();
-        self.orders.push_back(order)
+    pub(crate) fn add_order(&mut self, nodes: &mut Slab<O>, order: O) -> Handle {
+        let remaining = order.remaining();
+        self.total_quantity += remaining;
+        // Ranks run from 0 with no gaps past the last rebuild, so the new order's
+        // rank is the next index in the sums
+        if let Some(queue) = &mut self.queue {
+            queue.counts.push(1);
+            queue.quantities.push(remaining);
+        }
+        self.orders.push_back(nodes, order)
     }
 
-    /// Fills an order and returns true if fully filled.
+    /// Fills an order of this level with at least `fill` left. Returns None if the
+    /// handle is stale, otherwise the order if it was fully filled (and removed).
     #[inline(always)]
-    pub fn fill_order(&mut self, node_ptr: *mut Node<O>, order: &mut O, fill: O::N) -> bool {
+    pub(crate) fn fill_order(
+        &mut self,
+        nodes: &mut Slab<O>,
+        handle: Handle,
+        fill: O::N,
+    ) -> Option<Option<O>> {
+        let order = nodes.get_mut(handle)?;
         order.fill(fill);
         self.total_quantity -= fill;
-        if order.remaining() == O::N::default() {
-            self.orders.remove(node_ptr);
-            return true;
+        let filled = order.remaining() == O::N::default();
+        if let Some(queue) = &mut self.queue
+            && let Some(rank) = nodes.rank(handle)
+        {
+            let rank = rank as usize;
+            queue.quantities.sub(rank, fill);
+            if filled {
+                queue.counts.sub(rank, 1);
+            }
         }
-        false
+        if filled {
+            let removed = self.orders.remove(nodes, handle);
+            self.compact(nodes);
+            return Some(removed);
+        }
+        Some(None)
     }
 
+    /// Removes an order of this level and returns it, or None if the handle is
+    /// stale.
     #[inline(always)]
-    pub fn remove_order(&mut self, node_ptr: *mut Node<O>) {
-        if let Some(ref order) = self.orders.remove(node_ptr) {
-            self.total_quantity -= order.remaining();
+    pub(crate) fn remove_order(&mut self, nodes: &mut Slab<O>, handle: Handle) -> Option<O> {
+        let rank = self.queue.as_ref().and_then(|_| nodes.rank(handle));
+        let order = self.orders.remove(nodes, handle)?;
+        self.total_quantity -= order.remaining();
+        if let (Some(queue), Some(rank)) = (&mut self.queue, rank) {
+            let rank = rank as usize;
+            queue.counts.sub(rank, 1);
+            queue.quantities.sub(rank, order.remaining());
+            self.compact(nodes);
         }
+        Some(order)
     }
 
+    /// Rebuilds the queue sums once removed orders outnumber resting ones, so
+    /// their size stays proportional to the level's.
+    #[inline]
+    fn compact(&mut self, nodes: &mut Slab<O>) {
+        if let Some(queue) = &mut self.queue
+            && queue.counts.len() > 2 * self.orders.len() + 32
+        {
+            queue.rebuild(&mut self.orders, nodes);
+        }
+    }
+
+    /// Returns (orders ahead, quantity ahead) of the order in FIFO order, or
+    /// None if the handle is stale. Takes O(log n) once `track_queue` is called,
+    /// otherwise walks towards both ends at once, taking O(min(ahead, behind)) steps.
+    #[inline]
+    pub fn queue_position(&self, nodes: &Slab<O>, handle: Handle) -> Option<(usize, O::N)> {
+        if let Some(queue) = &self.queue {
+            let rank = nodes.rank(handle)? as usize;
+            let ahead = queue.counts.prefix(rank) as usize;
+            return Some((ahead, queue.quantities.prefix(rank)));
+        }
+        let (mut back, mut front) = self.orders.around(nodes, handle)?;
+        let mut ahead = (0, O::N::default());
+        let mut behind = (0, O::N::default());
+        loop {
+            let Some(order) = back.next() else {
+                return Some(ahead);
+            };
+            let Some(next) = front.next() else {
+                let remaining = nodes.get(handle)?.remaining();
+                return Some((
+                    self.len() - 1 - behind.0,
+                    self.total_quantity - remaining - behind.1,
+                ));
+            };
+            ahead.0 += 1;
+            ahead.1 += order.remaining();
+            behind.0 += 1;
+            behind.1 += next.remaining();
+        }
+    }
+
+    /// Returns a handle to the first order in FIFO order.
     #[inline(always)]
-    pub fn iter(&self) -> Iter<'_, O> {
-        self.orders.iter()
+    pub fn front(&self, nodes: &Slab<O>) -> Option<Handle> {
+        self.orders.front(nodes)
     }
 
+    /// Iterates the orders in FIFO order.
     #[inline(always)]
-    pub fn iter_mut(&mut self) -> IterMut<'_, O> {
-        self.orders.iter_mut()
+    pub fn iter<'a>(&self, nodes: &'a Slab<O>) -> Iter<'a, O> {
+        self.orders.iter(nodes)
+    }
+}
+
+impl<O: OrderInterface> Clone for Level<O> {
+    fn clone(&self) -> Self {
+        Self {
+            price: self.price,
+            orders: self.orders.clone(),
+            total_quantity: self.total_quantity,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+/// A level together with the slab holding its orders.
+pub struct LevelRef<'a, O: OrderInterface> {
+    level: &'a Level<O>,
+    nodes: &'a Slab<O>,
+}
+
+impl<'a, O: OrderInterface> LevelRef<'a, O> {
+    #[inline(always)]
+    pub(crate) fn new(level: &'a Level<O>, nodes: &'a Slab<O>) -> Self {
+        Self { level, nodes }
+    }
+
+    /// Iterates the orders in FIFO order.
+    #[inline(always)]
+    pub fn iter(&self) -> Iter<'a, O> {
+        self.level.iter(self.nodes)
+    }
+
+    /// Returns a handle to the first order in FIFO order.
+    #[inline(always)]
+    pub fn front(&self) -> Option<Handle> {
+        self.level.front(self.nodes)
+    }
+
+    /// Returns (orders ahead, quantity ahead) of the order, see `Level::queue_position`.
+    #[inline]
+    pub fn queue_position(&self, handle: Handle) -> Option<(usize, O::N)> {
+        self.level.queue_position(self.nodes, handle)
+    }
+}
+
+impl<O: OrderInterface> Clone for LevelRef<'_, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for LevelRef<'_, O> {}
+
+impl<O: OrderInterface> Deref for LevelRef<'_, O> {
+    type Target = Level<O>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Level<O> {
+        self.level
     }
 }
 
@@ -94,8 +272,9 @@ mod tests {
 
     #[test]
     fn test_add_order() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 100, 50));
+        level.add_order(&mut nodes, TestOrder::new("1", true, 100, 50));
         assert_eq!(level.total_quantity(), 50);
         assert_eq!(level.len(), 1);
         assert!(!level.is_empty());
@@ -103,75 +282,142 @@ mod tests {
 
     #[test]
     fn test_add_multiple_orders() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 100, 50));
-        level.add_order(TestOrder::new("2", true, 100, 30));
-        level.add_order(TestOrder::new("3", true, 100, 20));
+        level.add_order(&mut nodes, TestOrder::new("1", true, 100, 50));
+        level.add_order(&mut nodes, TestOrder::new("2", true, 100, 30));
+        level.add_order(&mut nodes, TestOrder::new("3", true, 100, 20));
         assert_eq!(level.total_quantity(), 100);
         assert_eq!(level.len(), 3);
     }
 
     #[test]
     fn test_remove_order() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 100, 50));
-        let node_ptr = level.add_order(TestOrder::new("2", true, 100, 30));
-        level.add_order(TestOrder::new("3", true, 100, 20));
-        level.remove_order(node_ptr);
+        level.add_order(&mut nodes, TestOrder::new("1", true, 100, 50));
+        let handle = level.add_order(&mut nodes, TestOrder::new("2", true, 100, 30));
+        level.add_order(&mut nodes, TestOrder::new("3", true, 100, 20));
+        level.remove_order(&mut nodes, handle);
         assert_eq!(level.total_quantity(), 70);
         assert_eq!(level.len(), 2);
     }
 
     #[test]
     fn test_remove_nonexistent_order() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 50, 50));
-        level.remove_order(std::ptr::null_mut());
+        let handle = level.add_order(&mut nodes, TestOrder::new("1", true, 50, 50));
+        level.add_order(&mut nodes, TestOrder::new("2", true, 50, 50));
+        level.remove_order(&mut nodes, handle);
+        assert!(level.remove_order(&mut nodes, handle).is_none());
+        assert_eq!(level.queue_position(&nodes, handle), None);
         assert_eq!(level.total_quantity(), 50);
         assert_eq!(level.len(), 1);
     }
 
     #[test]
     fn test_iter() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 100, 50));
-        level.add_order(TestOrder::new("2", true, 100, 30));
-        level.add_order(TestOrder::new("3", true, 100, 20));
+        level.add_order(&mut nodes, TestOrder::new("1", true, 100, 50));
+        level.add_order(&mut nodes, TestOrder::new("2", true, 100, 30));
+        level.add_order(&mut nodes, TestOrder::new("3", true, 100, 20));
 
-        let ids: Vec<&String> = level.iter().map(|o| o.id()).collect();
+        let ids: Vec<&String> = level.iter(&nodes).map(|o| o.id()).collect();
         assert_eq!(ids, vec!["1", "2", "3"]);
     }
 
     #[test]
-    fn test_iter_mut() {
+    fn test_queue_position() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        level.add_order(TestOrder::new("1", true, 100, 50));
-        level.add_order(TestOrder::new("2", true, 100, 30));
+        let n1 = level.add_order(&mut nodes, TestOrder::new("1", true, 100, 50));
+        let n2 = level.add_order(&mut nodes, TestOrder::new("2", true, 100, 30));
+        let n3 = level.add_order(&mut nodes, TestOrder::new("3", true, 100, 20));
+        let n4 = level.add_order(&mut nodes, TestOrder::new("4", true, 100, 10));
+        assert_eq!(level.queue_position(&nodes, n1), Some((0, 0)));
+        assert_eq!(level.queue_position(&nodes, n2), Some((1, 50)));
+        assert_eq!(level.queue_position(&nodes, n3), Some((2, 80)));
+        assert_eq!(level.queue_position(&nodes, n4), Some((3, 100)));
 
-        let count = level.iter_mut().count();
-        assert_eq!(count, 2);
+        level.remove_order(&mut nodes, n2);
+        assert_eq!(level.queue_position(&nodes, n3), Some((1, 50)));
+        assert_eq!(level.queue_position(&nodes, n4), Some((2, 70)));
+    }
+
+    #[test]
+    fn test_track_queue() {
+        // The same operations on a walked level and a tracked one, which starts
+        // tracking partway and removes enough orders to compact several times
+        let mut walked = (Slab::new(), Level::<TestOrder>::new(100));
+        let mut tracked = (Slab::new(), Level::<TestOrder>::new(100));
+        let mut handles = Vec::new();
+        let mut state = 7u64;
+        for step in 0..2_000 {
+            if step == 50 {
+                tracked.1.track_queue(&mut tracked.0);
+                assert!(tracked.1.tracks_queue());
+            }
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let roll = state >> 33;
+            if handles.is_empty() || roll.is_multiple_of(3) {
+                let order = TestOrder::new(&step.to_string(), true, 100, 1 + roll % 50);
+                let handle = walked.1.add_order(&mut walked.0, order.clone());
+                assert_eq!(tracked.1.add_order(&mut tracked.0, order), handle);
+                handles.push(handle);
+                continue;
+            }
+            let handle = handles[(roll / 3) as usize % handles.len()];
+            let remaining = walked.0.get(handle).unwrap().remaining();
+            let fill = 1 + roll % remaining;
+            if roll % 3 == 1 {
+                let removed = walked.1.fill_order(&mut walked.0, handle, fill).unwrap();
+                let same = tracked.1.fill_order(&mut tracked.0, handle, fill).unwrap();
+                assert_eq!(removed.is_some(), same.is_some());
+            } else {
+                walked.1.remove_order(&mut walked.0, handle).unwrap();
+                tracked.1.remove_order(&mut tracked.0, handle).unwrap();
+            }
+            handles.retain(|&h| walked.0.contains(h));
+            for &h in &handles {
+                assert_eq!(
+                    tracked.1.queue_position(&tracked.0, h),
+                    walked.1.queue_position(&walked.0, h)
+                );
+            }
+        }
+        // Compaction keeps the sums proportional to the orders resting
+        let queue = tracked.1.queue.as_ref().unwrap();
+        assert!(queue.counts.len() <= 2 * tracked.1.len() + 32);
     }
 
     #[test]
     fn test_fill_order_partial() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        let node_ptr = level.add_order(TestOrder::new("1", true, 100, 100));
-        let order = unsafe { &mut (*node_ptr).data };
-        let removed = level.fill_order(node_ptr, order, 30);
-        assert!(!removed);
+        let handle = level.add_order(&mut nodes, TestOrder::new("1", true, 100, 100));
+        let removed = level.fill_order(&mut nodes, handle, 30);
+        assert!(removed.unwrap().is_none());
         assert_eq!(level.total_quantity(), 70);
         assert_eq!(level.len(), 1);
     }
 
     #[test]
     fn test_fill_order_complete() {
+        let mut nodes = Slab::new();
         let mut level = Level::<TestOrder>::new(100);
-        let node_ptr = level.add_order(TestOrder::new("1", true, 100, 100));
-        let order = unsafe { &mut (*node_ptr).data };
-        let removed = level.fill_order(node_ptr, order, 100);
-        assert!(removed);
+        let handle = level.add_order(&mut nodes, TestOrder::new("1", true, 100, 100));
+        let removed = level.fill_order(&mut nodes, handle, 100);
+        assert_eq!(removed.unwrap().unwrap().id(), "1");
         assert_eq!(level.total_quantity(), 0);
         assert_eq!(level.len(), 0);
         assert!(level.is_empty());
+
+        // A stale handle is reported, not filled
+        assert!(level.fill_order(&mut nodes, handle, 10).is_none());
+        assert_eq!(level.total_quantity(), 0);
     }
 }
@@ -1,12 +1,44 @@
+mod analytics;
+mod bars;
+mod checksum;
+mod depth;
+mod estimate;
+mod eval;
+mod feed;
+mod fenwick;
+mod fix;
 mod hash;
+mod itch;
+mod journal;
+mod ladder;
 mod level;
 mod list;
+mod lobster;
 mod ob;
 mod order;
+mod report;
 mod side;
+mod snapshot;
+mod tape;
+mod validate;
 
-pub use level::Level;
-pub use list::List;
+pub use bars::{Bar, BarAggregator, BarKind, Clock};
+pub use checksum::Crc32;
+pub use depth::{Depth, DepthLevel, DepthOrder, OrderDepth};
+pub use estimate::FillEstimate;
+pub use eval::{EvalBuffer, MatchRef};
+pub use feed::{LevelUpdate, OrderEvent, OrderFeed};
+pub use fix::{FixCodec, FixError, FixMessage, FixRequest, FixRequestKind, FixTranslator};
+pub use itch::{ItchError, ItchMessage, ItchOrder, ItchReader, ItchReplay};
+pub use journal::{InstructionCodec, Journal, JournalError};
+pub use level::{Level, LevelRef};
+pub use list::{Handle, List, Slab};
+pub use lobster::{
+    LobsterError, LobsterEvent, LobsterLevels, LobsterMessage, LobsterOrder, LobsterReplay,
+};
 pub use ob::*;
 pub use order::OrderInterface;
+pub use report::{ExecReport, ExecStatus};
 pub use side::Side;
+pub use snapshot::{OrderCodec, SnapshotError};
+pub use tape::{Tape, Trade, TradeStats};
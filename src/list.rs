@@ -1,206 +1,394 @@
-use std::ptr;
+use std::marker::PhantomData;
 
-/// A node in the doubly linked list.
-pub struct Node<T> {
-    pub data: T,
-    pub prev: *mut Node<T>,
-    pub next: *mut Node<T>,
+/// Index of no node.
+const NIL: u32 = u32::MAX;
+
+/// Identifies a node in a `Slab`. A handle goes stale once its node is removed
+/// and stays stale after the slot is reused, so it can never reach another node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
 }
 
-impl<T> Node<T> {
-    #[inline]
-    fn new(data: T) -> Box<Self> {
-        Box::new(Node {
-            data,
-            prev: ptr::null_mut(),
-            next: ptr::null_mut(),
-        })
-    }
+/// A node in a doubly linked list, linked by slot index.
+#[derive(Clone)]
+struct Node<T> {
+    data: T,
+    prev: u32,
+    next: u32,
+    /// Position in push order within its list, see `List::renumber`.
+    rank: u32,
 }
 
-/// A doubly linked list using unsafe raw pointers.
-pub struct List<T> {
-    head: *mut Node<T>,
-    tail: *mut Node<T>,
-    length: usize,
+#[derive(Clone)]
+enum Entry<T> {
+    Occupied(Node<T>),
+    /// Links unused slots into a free list.
+    Vacant(u32),
 }
 
-impl<T> List<T> {
+#[derive(Clone)]
+struct Slot<T> {
+    /// Bumped whenever the slot is vacated.
+    generation: u32,
+    entry: Entry<T>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Slab
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Holds the nodes of any number of lists in one buffer and reuses the slots of
+/// removed nodes, so pushing to a list allocates only when every slot is in use.
+/// Clones keep every slot in place, so handles into the original reach the same
+/// nodes in the clone.
+#[derive(Clone)]
+pub struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    /// First vacant slot, or NIL.
+    free: u32,
+    len: usize,
+}
+
+impl<T> Slab<T> {
     #[inline]
     pub fn new() -> Self {
-        List {
-            head: ptr::null_mut(),
-            tail: ptr::null_mut(),
-            length: 0,
+        Self::with_capacity(0)
+    }
+
+    /// Creates a slab that holds `capacity` nodes before allocating.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity),
+            free: NIL,
+            len: 0,
         }
     }
 
+    /// Nodes in use.
     #[inline]
     pub fn len(&self) -> usize {
-        self.length
+        self.len
     }
 
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.length == 0
+        self.len == 0
     }
 
-    /// Returns pointer to the newly inserted node.
-    #[inline(always)]
-    pub fn push_back(&mut self, data: T) -> *mut Node<T> {
-        let new_node = Box::into_raw(Node::new(data));
-        unsafe {
-            if self.tail.is_null() {
-                self.head = new_node;
-            } else {
-                (*self.tail).next = new_node;
-                (*new_node).prev = self.tail;
-            }
-            self.tail = new_node;
+    /// Nodes the slab holds before allocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Makes room for at least `additional` more nodes in use.
+    pub fn reserve(&mut self, additional: usize) {
+        let vacant = self.slots.len() - self.len;
+        if additional > vacant {
+            self.slots.reserve(additional - vacant);
         }
-        self.length += 1;
-        new_node
     }
 
+    /// Returns true if the handle's node has not been removed.
     #[inline]
-    pub fn pop_front(&mut self) -> Option<*mut Node<T>> {
-        if self.head.is_null() || self.length == 0 {
-            return None;
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.node(handle).is_some()
+    }
+
+    #[inline]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.node(handle).map(|node| &node.data)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        match self.slots.get_mut(handle.index as usize) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(node),
+            }) if *generation == handle.generation => Some(&mut node.data),
+            _ => None,
         }
-        unsafe {
-            let old_head = self.head;
-            self.head = (*old_head).next;
-            if self.head.is_null() {
-                self.tail = ptr::null_mut();
-            } else {
-                (*self.head).prev = ptr::null_mut();
-            }
-            self.length -= 1;
-            Some(old_head)
+    }
+
+    /// Returns the node's rank in its list, or None if the handle is stale.
+    #[inline(always)]
+    pub(crate) fn rank(&self, handle: Handle) -> Option<u32> {
+        self.node(handle).map(|node| node.rank)
+    }
+
+    /// Returns the handle of the node linked after this one, or None if it is
+    /// the last in its list or the handle is stale.
+    #[inline(always)]
+    pub(crate) fn next(&self, handle: Handle) -> Option<Handle> {
+        let next = self.node(handle)?.next;
+        (next != NIL).then(|| self.handle(next))
+    }
+
+    #[inline(always)]
+    fn node(&self, handle: Handle) -> Option<&Node<T>> {
+        match self.slots.get(handle.index as usize) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(node),
+            }) if *generation == handle.generation => Some(node),
+            _ => None,
         }
     }
 
-    /// Removes node at pointer. Caller must ensure pointer is valid and in this list.
+    /// The node at a linked index, which lists only hold while it is occupied.
     #[inline(always)]
-    #[allow(clippy::not_unsafe_ptr_arg_deref)]
-    pub fn remove(&mut self, node_ptr: *mut Node<T>) -> Option<T> {
-        if node_ptr.is_null() || self.length == 0 {
-            return None;
+    fn at(&self, index: u32) -> &Node<T> {
+        match &self.slots[index as usize].entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant(_) => unreachable!("list links a vacant slot"),
+        }
+    }
+
+    #[inline(always)]
+    fn at_mut(&mut self, index: u32) -> &mut Node<T> {
+        match &mut self.slots[index as usize].entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant(_) => unreachable!("list links a vacant slot"),
         }
-        unsafe {
-            let prev = (*node_ptr).prev;
-            let next = (*node_ptr).next;
-            if prev.is_null() {
-                self.head = next;
-            } else {
-                (*prev).next = next;
-            }
-            if next.is_null() {
-                self.tail = prev;
-            } else {
-                (*next).prev = prev;
-            }
-            self.length -= 1;
-            Some(Box::from_raw(node_ptr).data)
+    }
+
+    #[inline(always)]
+    fn handle(&self, index: u32) -> Handle {
+        let generation = self.slots[index as usize].generation;
+        Handle { index, generation }
+    }
+
+    #[inline(always)]
+    fn insert(&mut self, node: Node<T>) -> u32 {
+        self.len += 1;
+        if self.free == NIL {
+            let index = self.slots.len() as u32;
+            assert!(index != NIL, "slab is full");
+            self.slots.push(Slot {
+                generation: 0,
+                entry: Entry::Occupied(node),
+            });
+            return index;
         }
+        let index = self.free;
+        let slot = &mut self.slots[index as usize];
+        let Entry::Vacant(next_free) = slot.entry else {
+            unreachable!("free list links an occupied slot");
+        };
+        self.free = next_free;
+        slot.entry = Entry::Occupied(node);
+        index
     }
-}
 
-impl<T> Default for List<T> {
-    fn default() -> Self {
-        Self::new()
+    #[inline(always)]
+    fn remove(&mut self, index: u32) -> Node<T> {
+        self.len -= 1;
+        let slot = &mut self.slots[index as usize];
+        slot.generation = slot.generation.wrapping_add(1);
+        let entry = std::mem::replace(&mut slot.entry, Entry::Vacant(self.free));
+        self.free = index;
+        match entry {
+            Entry::Occupied(node) => node,
+            Entry::Vacant(_) => unreachable!("list links a vacant slot"),
+        }
     }
 }
 
-impl<T> Drop for List<T> {
-    fn drop(&mut self) {
-        while self.pop_front().is_some() {}
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Iterators
+// List
 // ─────────────────────────────────────────────────────────────────────────────
 
-pub struct IntoIter<T>(List<T>);
-
-impl<T> Iterator for IntoIter<T> {
-    type Item = T;
+/// A doubly linked list whose nodes live in a `Slab`, which every call must pass.
+/// Dropping a list leaves its nodes in the slab.
+pub struct List<T> {
+    head: u32,
+    tail: u32,
+    length: usize,
+    /// Rank of the next node pushed.
+    next_rank: u32,
+    _marker: PhantomData<T>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .pop_front()
-            .map(|node_ptr| unsafe { Box::from_raw(node_ptr).data })
+impl<T> List<T> {
+    #[inline]
+    pub fn new() -> Self {
+        List {
+            head: NIL,
+            tail: NIL,
+            length: 0,
+            next_rank: 0,
+            _marker: PhantomData,
+        }
     }
-}
 
-pub struct Iter<'a, T> {
-    current: *mut Node<T>,
-    _marker: std::marker::PhantomData<&'a T>,
-}
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.length
+    }
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
 
+    /// Returns a handle to the newly inserted node.
     #[inline(always)]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current.is_null() {
-            return None;
-        }
-        unsafe {
-            let data = &(*self.current).data;
-            self.current = (*self.current).next;
-            Some(data)
+    pub fn push_back(&mut self, slab: &mut Slab<T>, data: T) -> Handle {
+        let index = slab.insert(Node {
+            data,
+            prev: self.tail,
+            next: NIL,
+            rank: self.next_rank,
+        });
+        self.next_rank = self.next_rank.wrapping_add(1);
+        if self.tail == NIL {
+            self.head = index;
+        } else {
+            slab.at_mut(self.tail).next = index;
         }
+        self.tail = index;
+        self.length += 1;
+        slab.handle(index)
     }
-}
 
-pub struct IterMut<'a, T> {
-    current: *mut Node<T>,
-    _marker: std::marker::PhantomData<&'a mut T>,
-}
+    #[inline]
+    pub fn pop_front(&mut self, slab: &mut Slab<T>) -> Option<T> {
+        if self.head == NIL {
+            return None;
+        }
+        Some(self.unlink(slab, self.head))
+    }
 
-impl<'a, T> Iterator for IterMut<'a, T> {
-    type Item = &'a mut T;
+    /// Removes a node of this list, or returns None if the handle is stale.
+    /// A live handle from another list would corrupt both, hence crate-only.
+    #[inline(always)]
+    pub(crate) fn remove(&mut self, slab: &mut Slab<T>, handle: Handle) -> Option<T> {
+        slab.node(handle)?;
+        Some(self.unlink(slab, handle.index))
+    }
 
     #[inline(always)]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current.is_null() {
-            return None;
+    fn unlink(&mut self, slab: &mut Slab<T>, index: u32) -> T {
+        let Node {
+            data, prev, next, ..
+        } = slab.remove(index);
+        if prev == NIL {
+            self.head = next;
+        } else {
+            slab.at_mut(prev).next = next;
         }
-        unsafe {
-            let data = &mut (*self.current).data;
-            let next = (*self.current).next;
-            self.current = next;
-            Some(data)
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            slab.at_mut(next).prev = prev;
         }
+        self.length -= 1;
+        data
     }
-}
 
-impl<T> IntoIterator for List<T> {
-    type Item = T;
-    type IntoIter = IntoIter<T>;
+    /// Ranks the nodes 0, 1, ... from the front, so later pushes continue from
+    /// `len()`. Ranks only increase towards the back, leaving gaps where nodes
+    /// were removed, until renumbered.
+    pub(crate) fn renumber(&mut self, slab: &mut Slab<T>) {
+        let mut index = self.head;
+        let mut rank = 0;
+        while index != NIL {
+            let node = slab.at_mut(index);
+            node.rank = rank;
+            index = node.next;
+            rank += 1;
+        }
+        self.next_rank = rank;
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter(self)
+    /// Returns a handle to the first node.
+    #[inline(always)]
+    pub fn front(&self, slab: &Slab<T>) -> Option<Handle> {
+        (self.head != NIL).then(|| slab.handle(self.head))
     }
-}
 
-impl<T> List<T> {
     #[inline]
-    pub fn iter(&self) -> Iter<'_, T> {
+    pub fn iter<'a>(&self, slab: &'a Slab<T>) -> Iter<'a, T> {
         Iter {
+            slab,
             current: self.head,
-            _marker: std::marker::PhantomData,
+            backward: false,
         }
     }
 
+    /// Returns iterators over the nodes before (nearest first) and after a node
+    /// of this list, or None if the handle is stale.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
-        IterMut {
-            current: self.head,
-            _marker: std::marker::PhantomData,
+    pub fn around<'a>(
+        &self,
+        slab: &'a Slab<T>,
+        handle: Handle,
+    ) -> Option<(Iter<'a, T>, Iter<'a, T>)> {
+        let node = slab.node(handle)?;
+        let before = Iter {
+            slab,
+            current: node.prev,
+            backward: true,
+        };
+        let after = Iter {
+            slab,
+            current: node.next,
+            backward: false,
+        };
+        Some((before, after))
+    }
+}
+
+impl<T> Clone for List<T> {
+    /// Clones the links only; clone the slab alongside to copy the nodes.
+    fn clone(&self) -> Self {
+        List {
+            head: self.head,
+            tail: self.tail,
+            length: self.length,
+            next_rank: self.next_rank,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Iterators
+// ─────────────────────────────────────────────────────────────────────────────
+
+pub struct Iter<'a, T> {
+    slab: &'a Slab<T>,
+    current: u32,
+    backward: bool,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current == NIL {
+            return None;
         }
+        let node = self.slab.at(self.current);
+        self.current = if self.backward { node.prev } else { node.next };
+        Some(&node.data)
     }
 }
 
@@ -208,6 +396,20 @@ impl<T> List<T> {
 mod tests {
     use super::*;
 
+    fn list(values: &[i32]) -> (List<i32>, Slab<i32>, Vec<Handle>) {
+        let mut slab = Slab::new();
+        let mut list = List::new();
+        let handles = values
+            .iter()
+            .map(|&v| list.push_back(&mut slab, v))
+            .collect();
+        (list, slab, handles)
+    }
+
+    fn values(list: &List<i32>, slab: &Slab<i32>) -> Vec<i32> {
+        list.iter(slab).copied().collect()
+    }
+
     #[test]
     fn test_new() {
         let list: List<i32> = List::new();
@@ -224,180 +426,150 @@ mod tests {
 
     #[test]
     fn test_push_back() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
+        let (list, slab, _) = list(&[1, 2, 3]);
         assert_eq!(list.len(), 3);
-        assert_eq!(list.iter().last().unwrap(), &3);
+        assert_eq!(list.iter(&slab).last().unwrap(), &3);
     }
 
     #[test]
     fn test_pop_front() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-
-        let node1 = list.pop_front().unwrap();
-        assert_eq!(unsafe { (*node1).data }, 1);
-        unsafe {
-            let _ = Box::from_raw(node1);
-        }
-
-        let node2 = list.pop_front().unwrap();
-        assert_eq!(unsafe { (*node2).data }, 2);
-        unsafe {
-            let _ = Box::from_raw(node2);
-        }
-
-        let node3 = list.pop_front().unwrap();
-        assert_eq!(unsafe { (*node3).data }, 3);
-        unsafe {
-            let _ = Box::from_raw(node3);
-        }
-
-        assert_eq!(list.pop_front(), None);
+        let (mut list, mut slab, _) = list(&[1, 2, 3]);
+        assert_eq!(list.pop_front(&mut slab), Some(1));
+        assert_eq!(list.pop_front(&mut slab), Some(2));
+        assert_eq!(list.pop_front(&mut slab), Some(3));
+        assert_eq!(list.pop_front(&mut slab), None);
         assert!(list.is_empty());
-        assert_eq!(list.len(), 0);
-    }
-
-    #[test]
-    fn test_into_iter() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-        let vec: Vec<i32> = list.into_iter().collect();
-        assert_eq!(vec, vec![1, 2, 3]);
-    }
-
-    #[test]
-    fn test_drop() {
-        let mut list = List::new();
-        for i in 0..100 {
-            list.push_back(i);
-        }
+        assert!(slab.is_empty());
     }
 
     #[test]
     fn test_iter() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&1, &2, &3]);
+        let (list, slab, _) = list(&[1, 2, 3]);
+        assert_eq!(values(&list, &slab), vec![1, 2, 3]);
         assert_eq!(list.len(), 3);
     }
 
     #[test]
-    fn test_iter_mut() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-        for item in list.iter_mut() {
-            *item *= 2;
+    fn test_get_mut() {
+        let (list, mut slab, handles) = list(&[1, 2, 3]);
+        for &handle in &handles {
+            *slab.get_mut(handle).unwrap() *= 2;
         }
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&2, &4, &6]);
-        assert_eq!(list.len(), 3);
+        assert_eq!(values(&list, &slab), vec![2, 4, 6]);
+        assert_eq!(slab.get(handles[1]), Some(&4));
     }
 
     #[test]
-    fn test_remove_null_pointer() {
-        let mut list = List::new();
-        list.push_back(1);
-        let result = list.remove(std::ptr::null_mut());
-        assert_eq!(result, None);
-        assert_eq!(list.len(), 1);
+    fn test_remove_stale_handle() {
+        let (mut list, mut slab, handles) = list(&[1, 2]);
+        assert_eq!(list.remove(&mut slab, handles[0]), Some(1));
+        assert_eq!(list.remove(&mut slab, handles[0]), None);
+        assert!(!slab.contains(handles[0]));
+
+        // The slot is reused, but the old handle still does not reach it
+        let handle = list.push_back(&mut slab, 3);
+        assert_ne!(handle, handles[0]);
+        assert_eq!(slab.get(handles[0]), None);
+        assert_eq!(list.remove(&mut slab, handles[0]), None);
+        assert_eq!(values(&list, &slab), vec![2, 3]);
+        assert_eq!(slab.len(), 2);
     }
 
     #[test]
     fn test_remove_head() {
-        let mut list = List::new();
-        let node1 = list.push_back(1);
-        list.push_back(2);
-        list.push_back(3);
-        let removed = list.remove(node1);
-        assert_eq!(removed, Some(1));
+        let (mut list, mut slab, handles) = list(&[1, 2, 3]);
+        assert_eq!(list.remove(&mut slab, handles[0]), Some(1));
         assert_eq!(list.len(), 2);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&2, &3]);
+        assert_eq!(values(&list, &slab), vec![2, 3]);
     }
 
     #[test]
     fn test_remove_tail() {
-        let mut list = List::new();
-        list.push_back(1);
-        list.push_back(2);
-        let node3 = list.push_back(3);
-        let removed = list.remove(node3);
-        assert_eq!(removed, Some(3));
+        let (mut list, mut slab, handles) = list(&[1, 2, 3]);
+        assert_eq!(list.remove(&mut slab, handles[2]), Some(3));
         assert_eq!(list.len(), 2);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&1, &2]);
+        assert_eq!(values(&list, &slab), vec![1, 2]);
     }
 
     #[test]
     fn test_remove_middle() {
-        let mut list = List::new();
-        list.push_back(1);
-        let node2 = list.push_back(2);
-        list.push_back(3);
-        let removed = list.remove(node2);
-        assert_eq!(removed, Some(2));
+        let (mut list, mut slab, handles) = list(&[1, 2, 3]);
+        assert_eq!(list.remove(&mut slab, handles[1]), Some(2));
         assert_eq!(list.len(), 2);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&1, &3]);
+        assert_eq!(values(&list, &slab), vec![1, 3]);
     }
 
     #[test]
     fn test_remove_only_node() {
-        let mut list = List::new();
-        let node1 = list.push_back(1);
-        let removed = list.remove(node1);
-        assert_eq!(removed, Some(1));
+        let (mut list, mut slab, handles) = list(&[1]);
+        assert_eq!(list.remove(&mut slab, handles[0]), Some(1));
         assert_eq!(list.len(), 0);
         assert!(list.is_empty());
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, Vec::<&i32>::new());
+        assert_eq!(values(&list, &slab), Vec::<i32>::new());
     }
 
     #[test]
-    fn test_remove_multiple_nodes() {
+    fn test_around() {
+        let (list, slab, handles) = list(&[1, 2, 3, 4]);
+        let (before, after) = list.around(&slab, handles[2]).unwrap();
+        assert_eq!(before.copied().collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(after.copied().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_slab_recycles_slots() {
+        let mut slab = Slab::with_capacity(2);
+        let capacity = slab.capacity();
+        let mut list = List::new();
+        let first = list.push_back(&mut slab, String::from("1"));
+        list.push_back(&mut slab, String::from("2"));
+        for i in 3..100 {
+            list.pop_front(&mut slab);
+            list.push_back(&mut slab, i.to_string());
+        }
+        assert_eq!(slab.capacity(), capacity);
+        assert_eq!(slab.len(), 2);
+        assert!(!slab.contains(first));
+        assert_eq!(list.iter(&slab).collect::<Vec<_>>(), vec!["98", "99"]);
+    }
+
+    #[test]
+    fn test_slab_reserve() {
+        let mut slab: Slab<i32> = Slab::new();
+        slab.reserve(10);
+        assert!(slab.capacity() >= 10);
+
+        // Vacant slots count towards the room reserved
         let mut list = List::new();
-        let node1 = list.push_back(1);
-        let node2 = list.push_back(2);
-        let node3 = list.push_back(3);
-        let node4 = list.push_back(4);
-        let node5 = list.push_back(5);
-
-        let removed = list.remove(node3);
-        assert_eq!(removed, Some(3));
+        let handles: Vec<_> = (0..10).map(|i| list.push_back(&mut slab, i)).collect();
+        for handle in handles {
+            list.remove(&mut slab, handle);
+        }
+        let capacity = slab.capacity();
+        slab.reserve(10);
+        assert_eq!(slab.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_remove_multiple_nodes() {
+        let (mut list, mut slab, handles) = list(&[1, 2, 3, 4, 5]);
+
+        assert_eq!(list.remove(&mut slab, handles[2]), Some(3));
         assert_eq!(list.len(), 4);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&1, &2, &4, &5]);
+        assert_eq!(values(&list, &slab), vec![1, 2, 4, 5]);
 
-        let removed = list.remove(node5);
-        assert_eq!(removed, Some(5));
+        assert_eq!(list.remove(&mut slab, handles[4]), Some(5));
         assert_eq!(list.len(), 3);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&1, &2, &4]);
+        assert_eq!(values(&list, &slab), vec![1, 2, 4]);
 
-        let removed = list.remove(node1);
-        assert_eq!(removed, Some(1));
+        assert_eq!(list.remove(&mut slab, handles[0]), Some(1));
         assert_eq!(list.len(), 2);
-        let vec: Vec<&i32> = list.iter().collect();
-        assert_eq!(vec, vec![&2, &4]);
+        assert_eq!(values(&list, &slab), vec![2, 4]);
 
-        let removed = list.remove(node2);
-        assert_eq!(removed, Some(2));
+        assert_eq!(list.remove(&mut slab, handles[1]), Some(2));
         assert_eq!(list.len(), 1);
 
-        let removed = list.remove(node4);
-        assert_eq!(removed, Some(4));
+        assert_eq!(list.remove(&mut slab, handles[3]), Some(4));
         assert_eq!(list.len(), 0);
         assert!(list.is_empty());
     }
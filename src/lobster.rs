@@ -0,0 +1,516 @@
+use crate::{
+    ob::{ApplyError, Instruction, OrderBook},
+    order::OrderInterface,
+};
+use std::{
+    fmt,
+    io::{self, BufRead},
+};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// LOBSTER
+//
+// A message file has one event per line:
+//   time (seconds after midnight), type, order id, size, price, direction
+// and the orderbook file has the top N levels after each event on the same line:
+//   ask price 1, ask size 1, bid price 1, bid size 1, ask price 2, ...
+// Prices are dollars * 10_000. Missing levels have size 0 and a dummy price.
+//
+// Orders placed before the files start are unknown, so the book is seeded from
+// the first orderbook line with one synthetic order per level (id `SYNTHETIC |
+// price`). Events on unknown ids are applied to the synthetic order at their
+// price. Levels deeper than N on that first line are unknown too: when one
+// becomes visible beyond the deepest seeded price, it is seeded the same way.
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Flag bit set in the ids of synthetic orders standing in for unknown ones.
+const SYNTHETIC: u64 = 1 << 63;
+
+/// A resting order from a LOBSTER message file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LobsterOrder {
+    id: u64,
+    is_buy: bool,
+    price: u64,
+    quantity: u64,
+    remaining: u64,
+}
+
+impl LobsterOrder {
+    #[inline]
+    pub fn new(id: u64, is_buy: bool, price: u64, quantity: u64) -> Self {
+        Self {
+            id,
+            is_buy,
+            price,
+            quantity,
+            remaining: quantity,
+        }
+    }
+
+    /// Returns whether this order stands in for liquidity placed before the files start.
+    #[inline]
+    pub fn is_synthetic(&self) -> bool {
+        self.id & SYNTHETIC != 0
+    }
+}
+
+impl OrderInterface for LobsterOrder {
+    type T = u64;
+    type N = u64;
+
+    #[inline(always)]
+    fn id(&self) -> &u64 {
+        &self.id
+    }
+
+    #[inline(always)]
+    fn is_buy(&self) -> bool {
+        self.is_buy
+    }
+
+    #[inline(always)]
+    fn price(&self) -> u64 {
+        self.price
+    }
+
+    #[inline(always)]
+    fn quantity(&self) -> u64 {
+        self.quantity
+    }
+
+    #[inline(always)]
+    fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    #[inline(always)]
+    fn fill(&mut self, quantity: u64) {
+        self.remaining -= quantity;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LobsterEvent {
+    /// 1: a new limit order.
+    Submit,
+    /// 2: part of an order was cancelled.
+    Cancel,
+    /// 3: the rest of an order was cancelled.
+    Delete,
+    /// 4: a visible order traded.
+    Execute,
+    /// 5: a hidden order traded. The visible book is unchanged.
+    ExecuteHidden,
+    /// 6: an auction trade. The visible book is unchanged.
+    Cross,
+    /// 7: trading halted or resumed.
+    Halt,
+}
+
+/// One line of a LOBSTER message file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LobsterMessage {
+    /// Nanoseconds after midnight.
+    pub time: u64,
+    pub event: LobsterEvent,
+    pub id: u64,
+    pub size: u64,
+    pub price: u64,
+    /// Side of the resting order, for executions too.
+    pub is_buy: bool,
+}
+
+impl LobsterMessage {
+    /// Parses a message line, or None if it is malformed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim_end().split(',');
+        let mut next = || fields.next();
+        let time = parse_time(next()?)?;
+        let event = match next()? {
+            "1" => LobsterEvent::Submit,
+            "2" => LobsterEvent::Cancel,
+            "3" => LobsterEvent::Delete,
+            "4" => LobsterEvent::Execute,
+            "5" => LobsterEvent::ExecuteHidden,
+            "6" => LobsterEvent::Cross,
+            "7" => LobsterEvent::Halt,
+            _ => return None,
+        };
+        let id = next()?.parse().ok()?;
+        let size = next()?.parse().ok()?;
+        let price = next()?.parse::<i64>().ok()?;
+        let is_buy = match next()? {
+            "1" => true,
+            "-1" => false,
+            _ => return None,
+        };
+        Some(Self {
+            time,
+            event,
+            id,
+            size,
+            // Halts carry a price of -1
+            price: price.max(0) as u64,
+            is_buy,
+        })
+    }
+}
+
+/// Parses `seconds[.fraction]` into nanoseconds.
+fn parse_time(s: &str) -> Option<u64> {
+    let (secs, frac) = s.split_once('.').unwrap_or((s, ""));
+    if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = frac.bytes().chain(std::iter::repeat(b'0')).take(9);
+    let nanos = nanos.fold(0, |acc, b| acc * 10 + (b - b'0') as u64);
+    Some(secs.parse::<u64>().ok()? * 1_000_000_000 + nanos)
+}
+
+/// One line of a LOBSTER orderbook file, as (price, size) per level, best first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LobsterLevels {
+    /// Levels per side in the file, including missing ones.
+    pub depth: usize,
+    pub asks: Vec<(u64, u64)>,
+    pub bids: Vec<(u64, u64)>,
+}
+
+impl LobsterLevels {
+    /// Parses an orderbook line, skipping missing levels, or None if malformed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let values = line
+            .trim_end()
+            .split(',')
+            .map(|v| v.parse::<i64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        if values.is_empty() || values.len() % 4 != 0 {
+            return None;
+        }
+        let level = |price: i64, size: i64| (size > 0).then_some((price as u64, size as u64));
+        Some(Self {
+            depth: values.len() / 4,
+            asks: values.chunks(4).filter_map(|c| level(c[0], c[1])).collect(),
+            bids: values.chunks(4).filter_map(|c| level(c[2], c[3])).collect(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LobsterError {
+    Io(io::Error),
+    /// A line could not be parsed.
+    Parse {
+        line: usize,
+    },
+    /// The message and orderbook files have different lengths.
+    Unpaired {
+        line: usize,
+    },
+    /// The book rejected the event on `line`, e.g. an execution of an unknown order.
+    Rejected {
+        line: usize,
+        error: ApplyError<LobsterOrder>,
+    },
+    /// After the event on `line`, the book's `level` (0 = best) on one side
+    /// differs from the orderbook file.
+    Diverged {
+        line: usize,
+        is_bid: bool,
+        level: usize,
+        expected: Option<(u64, u64)>,
+        actual: Option<(u64, u64)>,
+    },
+}
+
+impl fmt::Display for LobsterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LobsterError::Io(e) => write!(f, "lobster i/o error: {e}"),
+            LobsterError::Parse { line } => write!(f, "malformed lobster line {line}"),
+            LobsterError::Unpaired { line } => {
+                write!(
+                    f,
+                    "message and orderbook files differ in length at line {line}"
+                )
+            }
+            LobsterError::Rejected { line, error } => {
+                write!(f, "book rejected lobster line {line}: {error}")
+            }
+            LobsterError::Diverged {
+                line,
+                is_bid,
+                level,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "book diverged after line {line}: {} level {level} is {actual:?}, expected {expected:?}",
+                if *is_bid { "bid" } else { "ask" }
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LobsterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LobsterError::Io(e) => Some(e),
+            LobsterError::Rejected { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LobsterError {
+    fn from(e: io::Error) -> Self {
+        LobsterError::Io(e)
+    }
+}
+
+/// Rebuilds a book from LOBSTER messages. See the module notes on seeding.
+pub struct LobsterReplay {
+    book: OrderBook<LobsterOrder>,
+    /// Deepest seeded (bid, ask) prices, if the first line had no room for more.
+    floor: (Option<u64>, Option<u64>),
+    line: usize,
+}
+
+impl LobsterReplay {
+    /// Seeds a book from the orderbook line following the first message, which is
+    /// therefore not processed. Fails if the line repeats a price on one side.
+    pub fn seed(levels: &LobsterLevels) -> Result<Self, LobsterError> {
+        let mut replay = Self {
+            book: OrderBook::default(),
+            floor: (None, None),
+            line: 1,
+        };
+        let full =
+            |side: &[(u64, u64)]| (side.len() == levels.depth).then(|| side.last().unwrap().0);
+        replay.floor = (full(&levels.bids), full(&levels.asks));
+        for (is_bid, side) in [(true, &levels.bids), (false, &levels.asks)] {
+            for &(price, size) in side {
+                replay.add_synthetic(is_bid, price, size)?;
+            }
+        }
+        Ok(replay)
+    }
+
+    #[inline]
+    pub fn book(&self) -> &OrderBook<LobsterOrder> {
+        &self.book
+    }
+
+    /// Returns the line number of the last message processed.
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Applies the next message to the book.
+    pub fn process(&mut self, message: &LobsterMessage) -> Result<(), LobsterError> {
+        self.line += 1;
+        let id = if self.book.order(&message.id).is_some() {
+            message.id
+        } else {
+            SYNTHETIC | message.price
+        };
+        let instruction = match message.event {
+            LobsterEvent::Submit => {
+                let order =
+                    LobsterOrder::new(message.id, message.is_buy, message.price, message.size);
+                Instruction::Insert(order, message.size)
+            }
+            LobsterEvent::Cancel => Instruction::Reduce(id, message.size),
+            // A delete of an unknown order only removes its share of the level
+            LobsterEvent::Delete if id != message.id => Instruction::Reduce(id, message.size),
+            LobsterEvent::Delete => Instruction::Delete(id),
+            LobsterEvent::Execute => Instruction::Fill(id, message.size),
+            LobsterEvent::ExecuteHidden | LobsterEvent::Cross | LobsterEvent::Halt => {
+                return Ok(());
+            }
+        };
+        let line = self.line;
+        self.book
+            .apply(vec![instruction])
+            .map_err(|error| LobsterError::Rejected { line, error })
+    }
+
+    /// Compares the book's top levels with an orderbook line, after seeding any
+    /// newly visible level deeper than the first line showed.
+    pub fn check(&mut self, levels: &LobsterLevels) -> Result<(), LobsterError> {
+        for (is_bid, side) in [(true, &levels.bids), (false, &levels.asks)] {
+            let floor = if is_bid { self.floor.0 } else { self.floor.1 };
+            let Some(floor) = floor else {
+                continue;
+            };
+            for &(price, size) in side {
+                let beyond = if is_bid { price < floor } else { price > floor };
+                if beyond && self.book.order(&(SYNTHETIC | price)).is_none() {
+                    self.add_synthetic(is_bid, price, size)?;
+                }
+            }
+            if let Some(&(deepest, _)) = side.last().filter(|_| side.len() == levels.depth) {
+                let floor = if is_bid {
+                    floor.min(deepest)
+                } else {
+                    floor.max(deepest)
+                };
+                if is_bid {
+                    self.floor.0 = Some(floor);
+                } else {
+                    self.floor.1 = Some(floor);
+                }
+            }
+        }
+
+        let actual = (
+            self.book.top_bids(levels.depth),
+            self.book.top_asks(levels.depth),
+        );
+        for (is_bid, expected, actual) in [
+            (true, &levels.bids, &actual.0),
+            (false, &levels.asks, &actual.1),
+        ] {
+            for level in 0..levels.depth {
+                let (expected, actual) = (expected.get(level), actual.get(level));
+                if expected != actual {
+                    return Err(LobsterError::Diverged {
+                        line: self.line,
+                        is_bid,
+                        level,
+                        expected: expected.copied(),
+                        actual: actual.copied(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays a message file against its orderbook file, checking the book after
+    /// every message and stopping at the first divergence.
+    pub fn replay<M: BufRead, B: BufRead>(messages: M, orderbook: B) -> Result<Self, LobsterError> {
+        let mut messages = messages.lines();
+        let mut orderbook = orderbook.lines();
+        let mut replay: Option<Self> = None;
+        let mut line = 0;
+        loop {
+            line += 1;
+            let (message, levels) = match (messages.next(), orderbook.next()) {
+                (None, None) => break,
+                (Some(message), Some(levels)) => (message?, levels?),
+                _ => return Err(LobsterError::Unpaired { line }),
+            };
+            let parse_error = LobsterError::Parse { line };
+            let levels = LobsterLevels::parse(&levels).ok_or(parse_error)?;
+            let Some(replay) = &mut replay else {
+                replay = Some(Self::seed(&levels)?);
+                continue;
+            };
+            let message = LobsterMessage::parse(&message).ok_or(LobsterError::Parse { line })?;
+            replay.process(&message)?;
+            replay.check(&levels)?;
+        }
+        replay.ok_or(LobsterError::Unpaired { line })
+    }
+
+    #[inline]
+    fn add_synthetic(&mut self, is_bid: bool, price: u64, size: u64) -> Result<(), LobsterError> {
+        let order = LobsterOrder::new(SYNTHETIC | price, is_bid, price, size);
+        let line = self.line;
+        // Synthetic orders go to the back of their level, behind any real ones
+        self.book
+            .apply(vec![Instruction::Insert(order, size)])
+            .map_err(|error| LobsterError::Rejected { line, error })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two levels per side. Orders 1001 (ask 100.10) and 1003 (bid 100.00) were
+    /// placed before the files start, as was the 99.90 bid level, which only
+    /// becomes visible once better bids are gone.
+    const MESSAGES: &str = include_str!("../testdata/lobster_message.csv");
+    const ORDERBOOK: &str = include_str!("../testdata/lobster_orderbook.csv");
+
+    #[test]
+    fn test_parse() {
+        let message = LobsterMessage::parse("34200.004241176,1,2001,100,999500,1").unwrap();
+        assert_eq!(message.time, 34_200_004_241_176);
+        assert_eq!(message.event, LobsterEvent::Submit);
+        assert!(message.is_buy);
+        assert_eq!(
+            LobsterMessage::parse("34202.5,5,0,30,1000500,-1")
+                .unwrap()
+                .time,
+            34_202_500_000_000
+        );
+        assert!(LobsterMessage::parse("34200,9,1,1,1,1").is_none());
+
+        let levels =
+            LobsterLevels::parse("1001000,250,999000,100,1002000,200,-9999999999,0").unwrap();
+        assert_eq!(levels.depth, 2);
+        assert_eq!(levels.asks, vec![(1001000, 250), (1002000, 200)]);
+        assert_eq!(levels.bids, vec![(999000, 100)]);
+        assert!(LobsterLevels::parse("1,2,3").is_none());
+    }
+
+    #[test]
+    fn test_replay() {
+        let replay = LobsterReplay::replay(MESSAGES.as_bytes(), ORDERBOOK.as_bytes()).unwrap();
+        assert_eq!(replay.line(), 10);
+        let book = replay.book();
+        assert_eq!(
+            book.top_asks(5),
+            vec![(1000500, 50), (1001000, 200), (1002000, 200)]
+        );
+        assert_eq!(book.top_bids(5), vec![(999000, 100)]);
+        assert_eq!(book.order(&2003).unwrap().remaining(), 50);
+        assert!(book.order(&(SYNTHETIC | 999000)).unwrap().is_synthetic());
+    }
+
+    #[test]
+    fn test_divergence() {
+        // Line 6's best ask size altered from 250
+        let orderbook = ORDERBOOK.replacen(
+            "1001000,250,999000,100,1002000,200",
+            "1001000,240,999000,100,1002000,200",
+            1,
+        );
+        let err = LobsterReplay::replay(MESSAGES.as_bytes(), orderbook.as_bytes()).err();
+        assert!(matches!(
+            err,
+            Some(LobsterError::Diverged {
+                line: 6,
+                is_bid: false,
+                level: 0,
+                expected: Some((1001000, 240)),
+                actual: Some((1001000, 250)),
+            })
+        ));
+
+        let short = &ORDERBOOK[..ORDERBOOK.trim_end().rfind('\n').unwrap() + 1];
+        let err = LobsterReplay::replay(MESSAGES.as_bytes(), short.as_bytes()).err();
+        assert!(matches!(err, Some(LobsterError::Unpaired { line: 10 })));
+    }
+
+    #[test]
+    fn test_seed_rejects_repeated_price() {
+        let levels = LobsterLevels {
+            depth: 2,
+            asks: vec![(1001000, 100)],
+            bids: vec![(999000, 100), (999000, 50)],
+        };
+        assert!(matches!(
+            LobsterReplay::seed(&levels),
+            Err(LobsterError::Rejected {
+                line: 1,
+                error: ApplyError::DuplicateOrder { index: 0, .. }
+            })
+        ));
+    }
+}
@@ -1,11 +1,31 @@
-use crate::{hash::FxHashMap, list::Node, order::OrderInterface, side::Side};
+use crate::{
+    bars::{BarAggregator, Clock},
+    checksum::{Crc32, order_hash},
+    depth::{Depth, DepthOrder, OrderDepth},
+    estimate::FillEstimate,
+    feed::{LevelUpdate, OrderFeed, Sink},
+    hash::FxHashMap,
+    level::LevelRef,
+    list::Handle,
+    order::OrderInterface,
+    side::Side,
+    tape::Tape,
+    validate::Validator,
+};
+use std::fmt::{self, Write};
 
 /// A complete orderbook with bid and ask sides.
 pub struct OrderBook<O: OrderInterface> {
     bids: Side<O>,
     asks: Side<O>,
-    orders: FxHashMap<O::T, *mut Node<O>>,
+    /// Side (true for bids) and handle of every resting order.
+    orders: FxHashMap<O::T, (bool, Handle)>,
     temp: FxHashMap<O::T, O::N>,
+    /// Incrementally maintained state hash, if tracking is enabled.
+    hash: Option<u64>,
+    tape: Option<Tape<O>>,
+    /// Sequence of the last batch applied with `apply_batch`.
+    sequence: u64,
 }
 
 impl<O: OrderInterface> Default for OrderBook<O> {
@@ -15,31 +35,254 @@ impl<O: OrderInterface> Default for OrderBook<O> {
             asks: Side::new(false),
             orders: FxHashMap::default(),
             temp: FxHashMap::default(),
+            hash: None,
+            tape: None,
+            sequence: 0,
+        }
+    }
+}
+
+/// Deep copy: every order is cloned and keeps its queue position, so the copy
+/// can be evaluated and applied independently of the original.
+impl<O: OrderInterface + Clone> Clone for OrderBook<O> {
+    fn clone(&self) -> Self {
+        Self {
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            orders: self.orders.clone(),
+            temp: self.temp.clone(),
+            hash: self.hash,
+            tape: self.tape.clone(),
+            sequence: self.sequence,
         }
     }
 }
 
 /// An operation to apply to the orderbook.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O: serde::Serialize, O::T: serde::Serialize, O::N: serde::Serialize",
+        deserialize = "O: serde::Deserialize<'de>, O::T: serde::Deserialize<'de>, O::N: serde::Deserialize<'de>"
+    ))
+)]
 pub enum Op<O: OrderInterface> {
     Insert(O),
     Delete(O::T),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Msg {
     OrderNotFound,
     OrderAlreadyExists,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O: serde::Serialize, O::T: serde::Serialize, O::N: serde::Serialize",
+        deserialize = "O: serde::Deserialize<'de>, O::T: serde::Deserialize<'de>, O::N: serde::Deserialize<'de>"
+    ))
+)]
 pub enum Instruction<O: OrderInterface> {
     Insert(O, O::N),
     Delete(O::T),
     Fill(O::T, O::N),
+    /// Cancels part of a resting order without trading, keeping its queue
+    /// position. Never produced by `eval`; used to mirror external books.
+    Reduce(O::T, O::N),
     NoOp(Msg),
 }
 
+/// Instructions from one `eval_batch`, numbered so `apply_batch` can apply
+/// each batch exactly once and in order.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O: serde::Serialize, O::T: serde::Serialize, O::N: serde::Serialize",
+        deserialize = "O: serde::Deserialize<'de>, O::T: serde::Deserialize<'de>, O::N: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Batch<O: OrderInterface> {
+    pub sequence: u64,
+    pub instructions: Vec<Instruction<O>>,
+}
+
+/// Why `apply_batch` rejected a batch. `last` is the last sequence applied.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SequenceError {
+    /// The batch was already applied.
+    Duplicate { sequence: u64, last: u64 },
+    /// One or more batches before this one are missing.
+    Gap { sequence: u64, last: u64 },
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::Duplicate { sequence, last } => {
+                write!(f, "batch {sequence} already applied (last {last})")
+            }
+            SequenceError::Gap { sequence, last } => {
+                write!(
+                    f,
+                    "batch {sequence} does not follow last applied batch {last}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// Why `apply` rejected an instruction. `index` is its position in the batch.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ApplyError<O: OrderInterface> {
+    /// A `Delete`, `Fill` or `Reduce` names an order that is not resting.
+    UnknownOrder { index: usize, id: O::T },
+    /// A `Fill` or `Reduce` larger than the order's remaining quantity, or an `Insert`
+    /// resting more than the order has.
+    Overfill {
+        index: usize,
+        id: O::T,
+        quantity: O::N,
+        remaining: O::N,
+    },
+    /// An `Insert` reuses the id of a resting order.
+    DuplicateOrder { index: usize, id: O::T },
+    /// An `Insert`, `Fill` or `Reduce` with a zero quantity.
+    ZeroQuantity { index: usize, id: O::T },
+    /// A `Fill` hits an order at `price` while its side has a better `best` price,
+    /// trading through it.
+    PriceMismatch {
+        index: usize,
+        id: O::T,
+        price: O::N,
+        best: O::N,
+    },
+}
+
+impl<O: OrderInterface> ApplyError<O> {
+    #[inline]
+    pub fn index(&self) -> usize {
+        match self {
+            ApplyError::UnknownOrder { index, .. }
+            | ApplyError::Overfill { index, .. }
+            | ApplyError::DuplicateOrder { index, .. }
+            | ApplyError::ZeroQuantity { index, .. }
+            | ApplyError::PriceMismatch { index, .. } => *index,
+        }
+    }
+}
+
+impl<O: OrderInterface> fmt::Display for ApplyError<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::UnknownOrder { index, id } => {
+                write!(f, "instruction {index}: order {id} is not resting")
+            }
+            ApplyError::Overfill {
+                index,
+                id,
+                quantity,
+                remaining,
+            } => write!(
+                f,
+                "instruction {index}: {quantity} exceeds remaining {remaining} of order {id}"
+            ),
+            ApplyError::DuplicateOrder { index, id } => {
+                write!(f, "instruction {index}: order {id} already exists")
+            }
+            ApplyError::ZeroQuantity { index, id } => {
+                write!(f, "instruction {index}: zero quantity for order {id}")
+            }
+            ApplyError::PriceMismatch {
+                index,
+                id,
+                price,
+                best,
+            } => write!(
+                f,
+                "instruction {index}: fill of order {id} at {price} trades through {best}"
+            ),
+        }
+    }
+}
+
+impl<O: OrderInterface> std::error::Error for ApplyError<O> where Self: fmt::Debug {}
+
+/// Why `apply_batch` rejected a batch.
+pub enum BatchError<O: OrderInterface> {
+    Sequence(SequenceError),
+    Apply(ApplyError<O>),
+}
+
+impl<O: OrderInterface> fmt::Debug for BatchError<O>
+where
+    ApplyError<O>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Sequence(e) => f.debug_tuple("Sequence").field(e).finish(),
+            BatchError::Apply(e) => f.debug_tuple("Apply").field(e).finish(),
+        }
+    }
+}
+
+impl<O: OrderInterface> PartialEq for BatchError<O>
+where
+    ApplyError<O>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (BatchError::Sequence(a), BatchError::Sequence(b)) => a == b,
+            (BatchError::Apply(a), BatchError::Apply(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<O: OrderInterface> fmt::Display for BatchError<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Sequence(e) => e.fmt(f),
+            BatchError::Apply(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<O: OrderInterface> std::error::Error for BatchError<O> where Self: fmt::Debug {}
+
+impl<O: OrderInterface> From<SequenceError> for BatchError<O> {
+    fn from(e: SequenceError) -> Self {
+        BatchError::Sequence(e)
+    }
+}
+
+impl<O: OrderInterface> From<ApplyError<O>> for BatchError<O> {
+    fn from(e: ApplyError<O>) -> Self {
+        BatchError::Apply(e)
+    }
+}
+
+/// What `OrderBook::process` returns: the match, if any, and the instructions
+/// `eval` would have produced.
+pub type Processed<O> = (Option<Match<O>>, Vec<Instruction<O>>);
+
 /// A match between a taker and one or more makers.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "O::T: serde::Serialize, O::N: serde::Serialize",
+        deserialize = "O::T: serde::Deserialize<'de>, O::N: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Match<O: OrderInterface> {
     pub taker: (O::T, O::N),
     pub makers: Vec<(O::T, O::N)>,
@@ -50,19 +293,49 @@ pub struct Match<O: OrderInterface> {
 // ─────────────────────────────────────────────────────────────────────────────
 
 impl<O: OrderInterface> OrderBook<O> {
+    /// Creates a book that rests up to `capacity` orders per side without
+    /// allocating order nodes. Nodes of filled and deleted orders are reused.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut ob = Self::default();
+        ob.reserve(capacity);
+        ob
+    }
+
+    /// Creates a book whose sides keep the levels for prices `min..=max` in
+    /// steps of `tick` in arrays indexed by tick, with a bitmap to find the best
+    /// level. Suits instruments with a bounded price range; orders at other
+    /// prices rest in the usual tree. Panics if `tick` is zero or `max < min`.
+    pub fn with_ladder(min: O::N, max: O::N, tick: O::N) -> Self
+    where
+        O::N: TryInto<usize>,
+    {
+        Self {
+            bids: Side::with_ladder(true, min, max, tick),
+            asks: Side::with_ladder(false, min, max, tick),
+            ..Self::default()
+        }
+    }
+
+    /// Makes room for at least `additional` more resting orders per side.
+    pub fn reserve(&mut self, additional: usize) {
+        self.bids.reserve(additional);
+        self.asks.reserve(additional);
+        self.orders.reserve(additional);
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Getters
     // ─────────────────────────────────────────────────────────────────────────
 
     /// Returns an iterator over all bid levels, highest price first.
     #[inline]
-    pub fn bids(&self) -> impl Iterator<Item = &crate::level::Level<O>> {
+    pub fn bids(&self) -> impl Iterator<Item = LevelRef<'_, O>> {
         self.bids.iter()
     }
 
     /// Returns an iterator over all ask levels, lowest price first.
     #[inline]
-    pub fn asks(&self) -> impl Iterator<Item = &crate::level::Level<O>> {
+    pub fn asks(&self) -> impl Iterator<Item = LevelRef<'_, O>> {
         self.asks.iter()
     }
 
@@ -90,6 +363,134 @@ impl<O: OrderInterface> OrderBook<O> {
         self.asks.top(n)
     }
 
+    /// Fills `depth` with the top `n` levels of each side, reusing its buffers.
+    #[inline]
+    pub fn depth(&self, n: usize, depth: &mut Depth<O>) {
+        self.bids.depth(n, &mut depth.bids);
+        self.asks.depth(n, &mut depth.asks);
+    }
+
+    /// Returns every bid in priority order: highest price first, then FIFO.
+    #[inline]
+    pub fn bid_orders(&self) -> impl Iterator<Item = DepthOrder<'_, O>> {
+        self.bids.orders()
+    }
+
+    /// Returns every ask in priority order: lowest price first, then FIFO.
+    #[inline]
+    pub fn ask_orders(&self) -> impl Iterator<Item = DepthOrder<'_, O>> {
+        self.asks.orders()
+    }
+
+    /// Returns a Level 3 snapshot listing every resting order by side, price and priority.
+    #[inline]
+    pub fn l3(&self) -> OrderDepth<'_, O> {
+        OrderDepth {
+            bids: self.bid_orders().collect(),
+            asks: self.ask_orders().collect(),
+        }
+    }
+
+    /// Returns a CRC-32 over the top `n` levels of each side, interleaved best first
+    /// as `bid_price:bid_qty:ask_price:ask_qty:...` (the OKX layout). A side that runs
+    /// out of levels is skipped.
+    #[inline]
+    pub fn checksum(&self, n: usize) -> u32 {
+        let mut crc = Crc32::new();
+        let mut bids = self.bids.iter().take(n);
+        let mut asks = self.asks.iter().take(n);
+        let mut sep = "";
+        loop {
+            let (bid, ask) = (bids.next(), asks.next());
+            if bid.is_none() && ask.is_none() {
+                break;
+            }
+            for level in bid.into_iter().chain(ask) {
+                let _ = write!(crc, "{sep}{}:{}", level.price(), level.total_quantity());
+                sep = ":";
+            }
+        }
+        crc.finish()
+    }
+
+    /// Returns a CRC-32 over every resting order as `id:price:remaining`, bids then
+    /// asks, each in priority order. Sensitive to queue position, unlike `book_hash`.
+    #[inline]
+    pub fn l3_checksum(&self) -> u32 {
+        let mut crc = Crc32::new();
+        let mut sep = "";
+        for entry in self.bid_orders().chain(self.ask_orders()) {
+            let order = entry.order;
+            let _ = write!(
+                crc,
+                "{sep}{}:{}:{}",
+                order.id(),
+                entry.price,
+                order.remaining()
+            );
+            sep = ":";
+        }
+        crc.finish()
+    }
+
+    /// Starts maintaining `book_hash` incrementally, seeded from the current state.
+    #[inline]
+    pub fn track_hash(&mut self) {
+        self.hash = Some(self.compute_hash());
+    }
+
+    /// Returns the incrementally maintained book hash, if `track_hash` was called.
+    #[inline]
+    pub fn book_hash(&self) -> Option<u64> {
+        self.hash
+    }
+
+    /// Computes the book hash from scratch. It depends on every order's id, side,
+    /// price and remaining quantity but not on queue order, and matches `book_hash`
+    /// whenever tracking is enabled. The value is built from the `Display` output of
+    /// ids and numbers, so it is the same on every platform and build.
+    #[inline]
+    pub fn compute_hash(&self) -> u64 {
+        self.bid_orders()
+            .chain(self.ask_orders())
+            .fold(0, |hash, entry| {
+                hash ^ order_hash(entry.order, entry.order.remaining())
+            })
+    }
+
+    /// Estimates filling a market order of `quantity` against resting liquidity.
+    /// Read-only: ignores state pending from `eval` that has not been applied.
+    #[inline]
+    pub fn estimate_market(&self, is_buy: bool, quantity: O::N) -> FillEstimate<O> {
+        self.side(!is_buy).sweep(quantity, None)
+    }
+
+    /// Estimates the immediately marketable part of a limit order at `price`.
+    /// Read-only: ignores state pending from `eval` that has not been applied.
+    #[inline]
+    pub fn estimate_limit(&self, is_buy: bool, quantity: O::N, price: O::N) -> FillEstimate<O> {
+        self.side(!is_buy).sweep(quantity, Some(price))
+    }
+
+    /// Starts recording trades from applied fills, retaining the last `capacity`.
+    /// Replaces any existing tape.
+    #[inline]
+    pub fn enable_tape(&mut self, capacity: usize) {
+        self.tape = Some(Tape::new(capacity));
+    }
+
+    /// Returns the trade tape, if enabled.
+    #[inline]
+    pub fn tape(&self) -> Option<&Tape<O>> {
+        self.tape.as_ref()
+    }
+
+    /// Returns the trade tape mutably (e.g. to reset it), if enabled.
+    #[inline]
+    pub fn tape_mut(&mut self) -> Option<&mut Tape<O>> {
+        self.tape.as_mut()
+    }
+
     /// Returns the number of price levels on the bid side.
     #[inline]
     pub fn bid_depth(&self) -> usize {
@@ -117,9 +518,23 @@ impl<O: OrderInterface> OrderBook<O> {
     /// Returns a reference to the order with the given ID, if it exists.
     #[inline]
     pub fn order(&self, order_id: &O::T) -> Option<&O> {
-        self.orders
-            .get(order_id)
-            .map(|&ptr| unsafe { &(*ptr).data })
+        let &(is_buy, handle) = self.orders.get(order_id)?;
+        self.side(is_buy).order(handle)
+    }
+
+    /// Returns (orders ahead, quantity ahead) of the order within its level, if it
+    /// exists. O(log n) after `track_queue`, otherwise a walk of the level.
+    #[inline]
+    pub fn queue_position(&self, order_id: &O::T) -> Option<(usize, O::N)> {
+        let &(is_buy, handle) = self.orders.get(order_id)?;
+        self.side(is_buy).queue_position(handle)
+    }
+
+    /// Makes `queue_position` take O(log n) from now on, at O(log n) extra per
+    /// order added, filled or removed. See `Side::track_queue`.
+    pub fn track_queue(&mut self) {
+        self.bids.track_queue();
+        self.asks.track_queue();
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -135,52 +550,349 @@ impl<O: OrderInterface> OrderBook<O> {
         }
     }
 
-    /// Applies instructions to the orderbook, mutating state.
+    #[inline(always)]
+    pub(crate) fn side(&self, is_buy: bool) -> &Side<O> {
+        if is_buy { &self.bids } else { &self.asks }
+    }
+
+    /// Applies instructions to the orderbook, mutating state. Stops at the first
+    /// invalid instruction and returns it; the ones before it stay applied.
+    #[inline]
+    pub fn apply(&mut self, instructions: Vec<Instruction<O>>) -> Result<(), ApplyError<O>> {
+        self.apply_to(instructions, &mut ())
+    }
+
+    /// Like `apply`, but also rejects a fill that trades through a better price
+    /// resting on its side. Only suits batches that cannot rest a better price
+    /// before a fill, such as `eval` of a single op: `eval` does not match within
+    /// a batch, so an earlier insert may legitimately rest ahead of a later fill.
+    #[inline]
+    pub fn apply_strict(&mut self, instructions: Vec<Instruction<O>>) -> Result<(), ApplyError<O>> {
+        self.apply_checked(instructions, true, &mut ())
+    }
+
+    /// Applies instructions all-or-nothing: the whole batch is validated first and
+    /// nothing is applied if any instruction is invalid. Pending `eval` state is
+    /// discarded either way.
+    #[inline]
+    pub fn apply_atomic(&mut self, instructions: Vec<Instruction<O>>) -> Result<(), ApplyError<O>> {
+        self.validate(&instructions)?;
+        self.apply_to(instructions, &mut ())
+    }
+
+    /// Returns the first instruction `apply` would reject, discarding pending
+    /// `eval` state if there is one.
+    #[inline]
+    pub(crate) fn validate(
+        &mut self,
+        instructions: &[Instruction<O>],
+    ) -> Result<(), ApplyError<O>> {
+        let result = Validator::new(self).check(instructions);
+        if result.is_err() {
+            self.temp.clear();
+        }
+        result
+    }
+
+    /// Returns the sequence of the last batch applied with `apply_batch`.
     #[inline]
-    pub fn apply(&mut self, instructions: Vec<Instruction<O>>) {
-        for instruction in instructions {
-            match instruction {
-                Instruction::Insert(order, remaining) => self.apply_insert(order, remaining),
-                Instruction::Delete(order_id) => self.apply_delete(&order_id),
-                Instruction::Fill(order_id, quantity) => self.apply_fill(&order_id, quantity),
-                Instruction::NoOp(_) => {}
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Sets the last applied sequence, e.g. to resume after a batch known to be
+    /// applied. `restore` takes it from the snapshot.
+    #[inline]
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    /// Checks that `sequence` is the next batch to apply, without applying anything.
+    /// Lets a follower buffer batches that arrive early.
+    #[inline]
+    pub fn check_sequence(&self, sequence: u64) -> Result<(), SequenceError> {
+        let last = self.sequence;
+        if sequence <= last {
+            Err(SequenceError::Duplicate { sequence, last })
+        } else if sequence != last + 1 {
+            Err(SequenceError::Gap { sequence, last })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies a batch from `eval_batch` atomically if it is the next in sequence,
+    /// returning its sequence. Duplicates, batches after a gap and batches with an
+    /// invalid instruction are rejected and dropped without touching the book.
+    /// Plain `apply` does not advance the sequence.
+    #[inline]
+    pub fn apply_batch(&mut self, batch: Batch<O>) -> Result<u64, BatchError<O>> {
+        self.check_sequence(batch.sequence)?;
+        self.apply_atomic(batch.instructions)?;
+        self.sequence = batch.sequence;
+        Ok(batch.sequence)
+    }
+
+    /// Applies instructions and appends one `LevelUpdate` per level change they cause.
+    #[inline]
+    pub fn apply_with_updates(
+        &mut self,
+        instructions: Vec<Instruction<O>>,
+        updates: &mut Vec<LevelUpdate<O>>,
+    ) -> Result<(), ApplyError<O>> {
+        self.apply_to(instructions, updates)
+    }
+
+    /// Applies instructions and appends their market-by-order events to `feed`.
+    #[inline]
+    pub fn apply_with_events(
+        &mut self,
+        instructions: Vec<Instruction<O>>,
+        feed: &mut OrderFeed<O>,
+    ) -> Result<(), ApplyError<O>> {
+        self.apply_to(instructions, feed)
+    }
+
+    /// Applies instructions and feeds every fill they execute into `bars`.
+    #[inline]
+    pub fn apply_with_bars<C: Clock>(
+        &mut self,
+        instructions: Vec<Instruction<O>>,
+        bars: &mut BarAggregator<O, C>,
+    ) -> Result<(), ApplyError<O>> {
+        self.apply_to(instructions, bars)
+    }
+
+    #[inline(always)]
+    pub(crate) fn apply_to<S: Sink<O>>(
+        &mut self,
+        instructions: impl IntoIterator<Item = Instruction<O>>,
+        sink: &mut S,
+    ) -> Result<(), ApplyError<O>> {
+        self.apply_checked(instructions, false, sink)
+    }
+
+    /// Applies instructions, requiring fills to hit the best price if `strict`.
+    #[inline(always)]
+    fn apply_checked<S: Sink<O>>(
+        &mut self,
+        instructions: impl IntoIterator<Item = Instruction<O>>,
+        strict: bool,
+        sink: &mut S,
+    ) -> Result<(), ApplyError<O>> {
+        let mut result = Ok(());
+        for (index, instruction) in instructions.into_iter().enumerate() {
+            result = match instruction {
+                Instruction::Insert(order, remaining) => {
+                    self.apply_insert(index, order, remaining, sink)
+                }
+                Instruction::Delete(order_id) => self.apply_delete(index, order_id, sink),
+                Instruction::Fill(order_id, quantity) => {
+                    self.apply_fill(index, order_id, quantity, true, strict, sink)
+                }
+                Instruction::Reduce(order_id, quantity) => {
+                    self.apply_fill(index, order_id, quantity, false, false, sink)
+                }
+                Instruction::NoOp(_) => Ok(()),
+            };
+            if result.is_err() {
+                break;
             }
         }
         self.temp.clear();
+        result
     }
 
     #[inline(always)]
-    fn apply_insert(&mut self, mut order: O, remaining: O::N) {
-        let filled = order.quantity() - remaining;
+    fn apply_insert<S: Sink<O>>(
+        &mut self,
+        index: usize,
+        mut order: O,
+        remaining: O::N,
+        sink: &mut S,
+    ) -> Result<(), ApplyError<O>> {
+        let is_buy = order.is_buy();
+        let price = order.price();
+        if self.orders.contains_key(order.id()) {
+            let id = order.id().clone();
+            return Err(ApplyError::DuplicateOrder { index, id });
+        }
+        if remaining == O::N::default() {
+            let id = order.id().clone();
+            return Err(ApplyError::ZeroQuantity { index, id });
+        }
+        if overfills(&order, remaining) {
+            return Err(ApplyError::Overfill {
+                index,
+                id: order.id().clone(),
+                quantity: remaining,
+                remaining: order.remaining(),
+            });
+        }
+
+        let filled = order.remaining() - remaining;
         if filled > O::N::default() {
             order.fill(filled);
         }
         let id = order.id().clone();
-        let is_buy = order.is_buy();
-        let node_ptr = self.side_mut(is_buy).insert_order(order);
-        self.orders.insert(id, node_ptr);
+        rehash(&mut self.hash, &order, O::N::default(), order.remaining());
+        let handle = self.side_mut(is_buy).insert_order(order);
+        self.orders.insert(id, (is_buy, handle));
+        if S::ENABLED {
+            let side = self.side(is_buy);
+            sink.insert(side.order(handle).unwrap(), side.level(price).unwrap());
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn apply_delete<S: Sink<O>>(
+        &mut self,
+        index: usize,
+        order_id: O::T,
+        sink: &mut S,
+    ) -> Result<(), ApplyError<O>> {
+        let Some((is_buy, handle)) = self.orders.remove(&order_id) else {
+            return Err(ApplyError::UnknownOrder {
+                index,
+                id: order_id,
+            });
+        };
+        let removed = self.side_mut(is_buy).remove_order(handle);
+        if let Some(order) = removed {
+            rehash(&mut self.hash, &order, order.remaining(), O::N::default());
+            if S::ENABLED {
+                sink.delete(&order, self.side(is_buy).level(order.price()));
+            }
+        }
+        Ok(())
     }
 
+    /// Takes `quantity` off a resting order: a trade if `trade` (recorded on the
+    /// tape), otherwise a partial cancel.
     #[inline(always)]
-    fn apply_delete(&mut self, order_id: &O::T) {
-        let Some(&node_ptr) = self.orders.get(order_id) else {
-            return;
+    fn apply_fill<S: Sink<O>>(
+        &mut self,
+        index: usize,
+        order_id: O::T,
+        quantity: O::N,
+        trade: bool,
+        strict: bool,
+        sink: &mut S,
+    ) -> Result<(), ApplyError<O>> {
+        let Some(&(is_buy, handle)) = self.orders.get(&order_id) else {
+            return Err(ApplyError::UnknownOrder {
+                index,
+                id: order_id,
+            });
+        };
+        if quantity == O::N::default() {
+            return Err(ApplyError::ZeroQuantity {
+                index,
+                id: order_id,
+            });
+        }
+        let Some(data) = self.side(is_buy).order(handle) else {
+            return Err(ApplyError::UnknownOrder {
+                index,
+                id: order_id,
+            });
         };
-        let is_buy = unsafe { (*node_ptr).data.is_buy() };
-        self.side_mut(is_buy).remove_order(node_ptr);
-        self.orders.remove(order_id);
+        let (price, remaining) = (data.price(), data.remaining());
+        if quantity > remaining {
+            return Err(ApplyError::Overfill {
+                index,
+                id: order_id,
+                quantity,
+                remaining,
+            });
+        }
+        if strict
+            && let Some(best) = self.side(is_buy).best_price()
+            && best != price
+        {
+            return Err(ApplyError::PriceMismatch {
+                index,
+                id: order_id,
+                price,
+                best,
+            });
+        }
+        if !self.fill_resting(is_buy, handle, price, remaining, quantity, trade, sink) {
+            return Err(ApplyError::UnknownOrder {
+                index,
+                id: order_id,
+            });
+        }
+        Ok(())
     }
 
+    /// Takes `quantity` off the resting order behind `handle`, which has
+    /// `remaining` left at `price`. Returns false, changing nothing, if the
+    /// handle is stale.
     #[inline(always)]
-    fn apply_fill(&mut self, order_id: &O::T, quantity: O::N) {
-        let Some(&node_ptr) = self.orders.get(order_id) else {
-            return;
+    #[allow(clippy::too_many_arguments)]
+    fn fill_resting<S: Sink<O>>(
+        &mut self,
+        is_buy: bool,
+        handle: Handle,
+        price: O::N,
+        remaining: O::N,
+        quantity: O::N,
+        trade: bool,
+        sink: &mut S,
+    ) -> bool {
+        let Some(removed) = self.side_mut(is_buy).fill_order(handle, quantity) else {
+            return false;
+        };
+        if let Some(order) = &removed {
+            self.orders.remove(order.id());
+        }
+        // Borrow the side by field, so the hash and tape stay free to update
+        let side = if is_buy { &self.bids } else { &self.asks };
+        let order = match &removed {
+            Some(order) => order,
+            None => side.order(handle).unwrap(),
         };
-        let is_buy = unsafe { (*node_ptr).data.is_buy() };
-        let removed = self.side_mut(is_buy).fill_order(node_ptr, quantity);
-        if removed {
-            self.orders.remove(order_id);
+        rehash(&mut self.hash, order, remaining, order.remaining());
+        if trade && let Some(tape) = &mut self.tape {
+            tape.record(order, quantity);
+        }
+        if S::ENABLED {
+            let level = side.level(price);
+            if trade {
+                sink.fill(order, quantity, level);
+            } else {
+                sink.reduce(order, quantity, level);
+            }
         }
+        true
+    }
+
+    /// Inserts an order as-is at the back of its level, bypassing matching and
+    /// fill adjustment. Returns false if the id is already resting.
+    #[inline]
+    pub(crate) fn restore_order(&mut self, order: O) -> bool {
+        if self.orders.contains_key(order.id()) {
+            return false;
+        }
+        let (id, is_buy) = (order.id().clone(), order.is_buy());
+        rehash(&mut self.hash, &order, O::N::default(), order.remaining());
+        let handle = self.side_mut(is_buy).insert_order(order);
+        self.orders.insert(id, (is_buy, handle));
+        true
+    }
+
+    /// Removes every resting order, keeping the book's backend and capacity.
+    pub(crate) fn clear(&mut self) {
+        let mut orders = std::mem::take(&mut self.orders);
+        for (_, (is_buy, handle)) in orders.drain() {
+            if let Some(order) = self.side_mut(is_buy).remove_order(handle) {
+                rehash(&mut self.hash, &order, order.remaining(), O::N::default());
+            }
+        }
+        self.orders = orders;
+        self.temp.clear();
     }
 
     #[inline]
@@ -202,26 +914,49 @@ impl<O: OrderInterface> OrderBook<O> {
         (matches, instructions)
     }
 
+    /// Like `eval`, but numbers the instructions as the batch after the last one
+    /// applied. Apply it with `apply_batch` before evaluating the next batch.
+    #[inline]
+    pub fn eval_batch(&mut self, ops: Vec<Op<O>>) -> (Vec<Match<O>>, Batch<O>) {
+        let (matches, instructions) = self.eval(ops);
+        let batch = Batch {
+            sequence: self.sequence + 1,
+            instructions,
+        };
+        (matches, batch)
+    }
+
     #[inline(always)]
     pub fn eval_insert(&mut self, order: O) -> (Option<Match<O>>, Vec<Instruction<O>>) {
+        let mut instructions = Vec::with_capacity(16);
+        let mut makers = Vec::new();
+        let taker = self.eval_insert_to(order, &mut instructions, &mut makers);
+        (taker.map(|taker| Match { taker, makers }), instructions)
+    }
+
+    /// Matches `order` like `eval_insert`, appending its instructions to
+    /// `instructions` and its makers to `makers`. Returns the taker, if it matched.
+    #[inline(always)]
+    pub(crate) fn eval_insert_to(
+        &mut self,
+        order: O,
+        instructions: &mut Vec<Instruction<O>>,
+        makers: &mut Vec<(O::T, O::N)>,
+    ) -> Option<(O::T, O::N)> {
         if self.orders.contains_key(order.id()) {
-            return self.eval_insert_duplicate();
+            instructions.push(Self::eval_insert_duplicate());
+            return None;
         }
 
         let mut remaining_quantity = order.remaining();
         let mut taker_quantity = O::N::default();
-        let mut maker_quantities = Vec::new();
-        let mut instructions = Vec::with_capacity(16);
+        let start = instructions.len();
         let is_buy = order.is_buy();
         let price = order.price();
 
-        let opposite_side = if is_buy {
-            &mut self.asks
-        } else {
-            &mut self.bids
-        };
+        let opposite_side = if is_buy { &self.asks } else { &self.bids };
 
-        'outer: for level in opposite_side.iter_mut() {
+        'outer: for level in opposite_side.iter() {
             let dominated = if is_buy {
                 price < level.price()
             } else {
@@ -230,7 +965,7 @@ impl<O: OrderInterface> OrderBook<O> {
             if dominated {
                 break;
             }
-            for resting_order in level.iter_mut() {
+            for resting_order in level.iter() {
                 if remaining_quantity == O::N::default() {
                     break 'outer;
                 }
@@ -249,33 +984,30 @@ impl<O: OrderInterface> OrderBook<O> {
                     resting_order.id().clone(),
                     taken_quantity,
                 ));
-                maker_quantities.push((resting_order.id().clone(), taken_quantity));
+                makers.push((resting_order.id().clone(), taken_quantity));
                 self.temp
                     .insert(resting_order.id().clone(), remaining - taken_quantity);
             }
         }
 
-        let match_result = if taker_quantity > O::N::default() {
-            Some(Match {
-                taker: (order.id().clone(), taker_quantity),
-                makers: maker_quantities,
-            })
+        let taker = if taker_quantity > O::N::default() {
+            Some((order.id().clone(), taker_quantity))
         } else {
             None
         };
 
         if remaining_quantity > O::N::default() {
             instructions.push(Instruction::Insert(order, remaining_quantity));
-            instructions.rotate_right(1);
+            instructions[start..].rotate_right(1);
         }
 
-        (match_result, instructions)
+        taker
     }
 
     #[cold]
     #[inline(never)]
-    fn eval_insert_duplicate(&self) -> (Option<Match<O>>, Vec<Instruction<O>>) {
-        (None, vec![Instruction::NoOp(Msg::OrderAlreadyExists)])
+    fn eval_insert_duplicate() -> Instruction<O> {
+        Instruction::NoOp(Msg::OrderAlreadyExists)
     }
 
     #[inline(always)]
@@ -292,17 +1024,151 @@ impl<O: OrderInterface> OrderBook<O> {
     fn eval_cancel_not_found() -> Instruction<O> {
         Instruction::NoOp(Msg::OrderNotFound)
     }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Process
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Matches and applies one op in a single pass, returning what `eval` of the
+    /// op returns, and failing where `apply` of its instructions would. Fills
+    /// reach resting orders by handle, without the pending state `eval` keeps or
+    /// the id lookup `apply` repeats, so this suits single-writer books that
+    /// never evaluate speculatively. Pending `eval` state is discarded.
+    ///
+    /// `O: Clone` because the `Insert` returned holds a copy of the order rested.
+    pub fn process(&mut self, op: Op<O>) -> Result<Processed<O>, ApplyError<O>>
+    where
+        O: Clone,
+    {
+        if !self.temp.is_empty() {
+            self.temp.clear();
+        }
+        match op {
+            Op::Insert(order) => self.process_insert(order),
+            Op::Delete(order_id) => {
+                let instruction = match self.apply_delete(0, order_id.clone(), &mut ()) {
+                    Ok(()) => Instruction::Delete(order_id),
+                    Err(_) => Self::eval_cancel_not_found(),
+                };
+                Ok((None, vec![instruction]))
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn process_insert(&mut self, order: O) -> Result<Processed<O>, ApplyError<O>>
+    where
+        O: Clone,
+    {
+        if self.orders.contains_key(order.id()) {
+            return Ok((None, vec![Self::eval_insert_duplicate()]));
+        }
+
+        let zero = O::N::default();
+        let mut remaining_quantity = order.remaining();
+        let mut taker_quantity = zero;
+        let mut maker_quantities = Vec::new();
+        let mut instructions = Vec::with_capacity(16);
+        let is_buy = order.is_buy();
+        let price = order.price();
+        let first = |level: LevelRef<'_, O>| (level.price(), level.front());
+        let mut next = self.side(!is_buy).iter().next().map(first);
+
+        'outer: while remaining_quantity > zero
+            && let Some((level_price, front)) = next
+        {
+            let dominated = if is_buy {
+                price < level_price
+            } else {
+                price > level_price
+            };
+            if dominated {
+                break;
+            }
+
+            let mut cursor = front;
+            while let Some(handle) = cursor {
+                if remaining_quantity == zero {
+                    break 'outer;
+                }
+                let opposite_side = self.side(!is_buy);
+                cursor = opposite_side.next_order(handle);
+                let resting_order = opposite_side.order(handle).unwrap();
+                let remaining = resting_order.remaining();
+                if remaining == zero {
+                    continue;
+                }
+                let taken_quantity = remaining_quantity.min(remaining);
+                remaining_quantity -= taken_quantity;
+                taker_quantity += taken_quantity;
+                let id = resting_order.id().clone();
+                instructions.push(Instruction::Fill(id.clone(), taken_quantity));
+                maker_quantities.push((id, taken_quantity));
+                self.fill_resting(
+                    !is_buy,
+                    handle,
+                    level_price,
+                    remaining,
+                    taken_quantity,
+                    true,
+                    &mut (),
+                );
+            }
+            // Every order on the level was filled, so it is gone: find the
+            // next one from its price instead of from the best
+            next = self.side(!is_buy).after(level_price).map(first);
+        }
+
+        let match_result = if taker_quantity > zero {
+            Some(Match {
+                taker: (order.id().clone(), taker_quantity),
+                makers: maker_quantities,
+            })
+        } else {
+            None
+        };
+
+        if remaining_quantity > zero {
+            instructions.insert(0, Instruction::Insert(order.clone(), remaining_quantity));
+            self.apply_insert(0, order, remaining_quantity, &mut ())?;
+        }
+
+        Ok((match_result, instructions))
+    }
+}
+
+/// Updates the incremental book hash for an order whose remaining quantity went
+/// from `old` to `new`. Zero means the order is not resting.
+#[inline(always)]
+fn rehash<O: OrderInterface>(hash: &mut Option<u64>, order: &O, old: O::N, new: O::N) {
+    let Some(hash) = hash else {
+        return;
+    };
+    let zero = O::N::default();
+    if old > zero {
+        *hash ^= order_hash(order, old);
+    }
+    if new > zero {
+        *hash ^= order_hash(order, new);
+    }
+}
+
+/// Returns true if an `Insert` of `order` resting `remaining` would take more
+/// than the order has left.
+#[inline(always)]
+pub(crate) fn overfills<O: OrderInterface>(order: &O, remaining: O::N) -> bool {
+    remaining > order.remaining()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::order::TestOrder;
+    use crate::{bars::BarKind, feed::OrderEvent, order::TestOrder};
 
     fn setup_order(ob: &mut OrderBook<TestOrder>, id: &str, is_buy: bool, price: u64, qty: u64) {
         let order = TestOrder::new(id, is_buy, price, qty);
-        let node_ptr = ob.side_mut(is_buy).insert_order(order);
-        ob.orders.insert(String::from(id), node_ptr);
+        let handle = ob.side_mut(is_buy).insert_order(order);
+        ob.orders.insert(String::from(id), (is_buy, handle));
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -380,6 +1246,51 @@ mod tests {
         assert_eq!(levels.len(), 3);
     }
 
+    #[test]
+    fn test_depth_snapshot() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "b1", true, 100, 50);
+        setup_order(&mut ob, "b2", true, 100, 30);
+        setup_order(&mut ob, "b3", true, 90, 20);
+        setup_order(&mut ob, "s1", false, 110, 40);
+
+        let mut depth = Depth::with_capacity(5);
+        ob.depth(5, &mut depth);
+        assert_eq!(depth.bids.len(), 2);
+        assert_eq!(depth.bids[0].price, 100);
+        assert_eq!(depth.bids[0].orders, 2);
+        assert_eq!(depth.bids[1].cumulative_quantity, 100);
+        assert_eq!(depth.bids[1].cumulative_notional, 9800);
+        assert_eq!(depth.asks.len(), 1);
+        assert_eq!(depth.asks[0].cumulative_notional, 4400);
+
+        ob.apply(vec![Instruction::Delete(String::from("s1"))])
+            .unwrap();
+        ob.depth(5, &mut depth);
+        assert!(depth.asks.is_empty());
+    }
+
+    #[test]
+    fn test_l3_snapshot() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "b1", true, 90, 50);
+        setup_order(&mut ob, "b2", true, 100, 30);
+        setup_order(&mut ob, "b3", true, 100, 20);
+        setup_order(&mut ob, "s1", false, 120, 40);
+        setup_order(&mut ob, "s2", false, 110, 10);
+
+        let l3 = ob.l3();
+        let bids: Vec<(u64, usize, &str)> = l3
+            .bids
+            .iter()
+            .map(|o| (o.price, o.position, o.order.id().as_str()))
+            .collect();
+        assert_eq!(bids, vec![(100, 0, "b2"), (100, 1, "b3"), (90, 0, "b1")]);
+        let asks: Vec<&str> = l3.asks.iter().map(|o| o.order.id().as_str()).collect();
+        assert_eq!(asks, vec!["s2", "s1"]);
+        assert_eq!(l3.bids.len() + l3.asks.len(), ob.len());
+    }
+
     #[test]
     fn test_depth() {
         let mut ob = OrderBook::<TestOrder>::default();
@@ -407,6 +1318,248 @@ mod tests {
         assert_eq!(ob.len(), 3);
     }
 
+    #[test]
+    fn test_with_capacity_reuses_nodes() {
+        let mut ob = OrderBook::<TestOrder>::with_capacity(2);
+        let capacity = (ob.bids.capacity(), ob.asks.capacity());
+        assert!(capacity.0 >= 2 && capacity.1 >= 2);
+        for round in 0..10 {
+            let (s, b) = (format!("s{round}"), format!("b{round}"));
+            let (_, instructions) = ob.eval(vec![
+                Op::Insert(TestOrder::new(&s, false, 100, 10)),
+                Op::Insert(TestOrder::new(&b, true, 99, 10)),
+                Op::Insert(TestOrder::new("t", true, 100, 4)),
+            ]);
+            ob.apply(instructions).unwrap();
+            let (_, instructions) = ob.eval(vec![Op::Delete(s), Op::Delete(b)]);
+            ob.apply(instructions).unwrap();
+        }
+        assert!(ob.is_empty());
+        assert_eq!((ob.bids.capacity(), ob.asks.capacity()), capacity);
+
+        // Orders left resting are dropped with the book
+        for i in 0..=capacity.0 {
+            setup_order(&mut ob, &format!("b{i}"), true, 100 + i as u64 % 2, 50);
+        }
+        assert!(ob.bids.capacity() > capacity.0);
+    }
+
+    #[test]
+    fn test_book_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "b1", true, 100, 50);
+        assert_send(&ob);
+        let ob = std::thread::spawn(move || ob).join().unwrap();
+        assert_eq!(ob.queue_position(&String::from("b1")), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_ladder_matches_tree() {
+        let mut tree = OrderBook::<TestOrder>::default();
+        let mut ladder = OrderBook::<TestOrder>::with_ladder(90, 110, 2);
+        // Prices off the ladder (odd or out of range) rest in the tree
+        let prices = [100, 98, 101, 120, 96, 100, 85, 102, 99, 104];
+        for (round, &price) in prices.iter().enumerate() {
+            let side = round % 3 == 0;
+            let ops = || {
+                vec![
+                    Op::Insert(TestOrder::new(&format!("b{round}"), true, price - 6, 5)),
+                    Op::Insert(TestOrder::new(&format!("s{round}"), false, price, 7)),
+                    Op::Insert(TestOrder::new(&format!("t{round}"), side, price - 3, 9)),
+                    Op::Delete(format!("s{}", round / 2)),
+                ]
+            };
+            let (tree_matches, instructions) = tree.eval(ops());
+            let (ladder_matches, ladder_instructions) = ladder.eval(ops());
+            assert_eq!(ladder_instructions, instructions);
+            let matched = |matches: Vec<Match<TestOrder>>| -> Vec<_> {
+                matches.into_iter().map(|m| (m.taker, m.makers)).collect()
+            };
+            assert_eq!(matched(ladder_matches), matched(tree_matches));
+            tree.apply(instructions).unwrap();
+            ladder.apply(ladder_instructions).unwrap();
+
+            assert_eq!(ladder.top_bids(usize::MAX), tree.top_bids(usize::MAX));
+            assert_eq!(ladder.top_asks(usize::MAX), tree.top_asks(usize::MAX));
+            assert_eq!(
+                (ladder.best_bid(), ladder.best_ask()),
+                (tree.best_bid(), tree.best_ask())
+            );
+            let orders = |ob: &OrderBook<TestOrder>| -> Vec<TestOrder> {
+                ob.bid_orders()
+                    .chain(ob.ask_orders())
+                    .map(|o| o.order.clone())
+                    .collect()
+            };
+            assert_eq!(orders(&ladder), orders(&tree));
+        }
+        assert!(!tree.is_empty());
+        assert_eq!(ladder.len(), tree.len());
+    }
+
+    /// A seeded stream of inserts around 100 on both sides, some partly filled,
+    /// cancels of live and unknown ids, and duplicate ids.
+    fn random_ops(seed: u64, count: usize) -> Vec<Op<TestOrder>> {
+        let mut state = seed;
+        let mut next = |n: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        (0..count)
+            .map(|i| match next(10) {
+                0..=1 => Op::Delete(format!("o{}", next(i as u64 + 5))),
+                roll => {
+                    let id = if roll == 2 {
+                        format!("o{}", next(i as u64 + 1))
+                    } else {
+                        format!("o{i}")
+                    };
+                    let is_buy = next(2) == 0;
+                    let price = 95 + next(11);
+                    let quantity = 1 + next(30);
+                    let mut order = TestOrder::new(&id, is_buy, price, quantity);
+                    // Some arrive partly filled
+                    if roll == 3 {
+                        order.fill(next(quantity));
+                    }
+                    Op::Insert(order)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_process_matches_eval_and_apply() {
+        let backends: [fn() -> OrderBook<TestOrder>; 2] =
+            [OrderBook::default, || OrderBook::with_ladder(90, 110, 1)];
+        for new_book in backends {
+            for seed in 0..8 {
+                let mut two_phase = new_book();
+                let mut fused = new_book();
+                for ob in [&mut two_phase, &mut fused] {
+                    ob.track_hash();
+                    ob.enable_tape(16);
+                }
+                fused.track_queue();
+                for (op, same_op) in random_ops(seed, 300).into_iter().zip(random_ops(seed, 300)) {
+                    let (matches, instructions) = two_phase.eval(vec![op]);
+                    let applied = two_phase.apply(instructions.clone());
+                    let processed = fused.process(same_op);
+                    let (fused_match, fused_instructions) = match (applied, processed) {
+                        (Ok(()), Ok(processed)) => processed,
+                        (applied, processed) => {
+                            assert_eq!(applied.err(), processed.err());
+                            continue;
+                        }
+                    };
+                    assert_eq!(fused_instructions, instructions);
+                    let matched = |m: Option<Match<TestOrder>>| m.map(|m| (m.taker, m.makers));
+                    assert_eq!(matched(fused_match), matched(matches.into_iter().next()));
+                }
+
+                assert_eq!(fused.book_hash(), two_phase.book_hash());
+                assert_eq!(fused.book_hash(), Some(fused.compute_hash()));
+                let orders = |ob: &OrderBook<TestOrder>| -> Vec<TestOrder> {
+                    ob.bid_orders()
+                        .chain(ob.ask_orders())
+                        .map(|o| o.order.clone())
+                        .collect()
+                };
+                assert!(!orders(&fused).is_empty());
+                assert_eq!(orders(&fused), orders(&two_phase));
+                for order in orders(&fused) {
+                    assert_eq!(
+                        fused.queue_position(order.id()),
+                        two_phase.queue_position(order.id())
+                    );
+                }
+                let (tape, fused_tape) = (two_phase.tape().unwrap(), fused.tape().unwrap());
+                assert!(tape.stats().count > 0);
+                assert_eq!(fused_tape.stats(), tape.stats());
+                assert!(fused_tape.trades().eq(tape.trades()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_discards_pending_eval() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.process(Op::Insert(TestOrder::new("s1", false, 100, 10)))
+            .unwrap();
+        let _ = ob.eval(vec![Op::Delete(String::from("s1"))]);
+
+        let (m, instructions) = ob
+            .process(Op::Insert(TestOrder::new("b1", true, 100, 4)))
+            .unwrap();
+        assert_eq!(m.unwrap().makers, [(String::from("s1"), 4)]);
+        assert_eq!(instructions, [Instruction::Fill(String::from("s1"), 4)]);
+        assert_eq!(ob.best_ask(), Some((100, 6)));
+        assert_eq!(
+            ob.process(Op::Delete(String::from("x"))).unwrap().1,
+            [Instruction::NoOp(Msg::OrderNotFound)]
+        );
+
+        // Orders filled before they arrive match and rest what they have left
+        let mut order = TestOrder::new("b2", true, 100, 20);
+        order.fill(5);
+        let (m, instructions) = ob.process(Op::Insert(order.clone())).unwrap();
+        assert_eq!(m.unwrap().makers, [(String::from("s1"), 6)]);
+        assert_eq!(
+            instructions,
+            [
+                Instruction::Insert(order, 9),
+                Instruction::Fill(String::from("s1"), 6)
+            ]
+        );
+        assert_eq!(ob.order(&String::from("b2")).unwrap().remaining(), 9);
+        assert_eq!(ob.best_bid(), Some((100, 9)));
+
+        let mut order = TestOrder::new("b3", true, 90, 20);
+        order.fill(5);
+        ob.process(Op::Insert(order)).unwrap();
+        assert_eq!(ob.best_bid(), Some((100, 9)));
+        assert_eq!(ob.order(&String::from("b3")).unwrap().remaining(), 15);
+    }
+
+    #[test]
+    fn test_clone_is_deep() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.track_hash();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 100, 10)),
+            Op::Insert(TestOrder::new("s2", false, 100, 20)),
+            Op::Insert(TestOrder::new("s3", false, 100, 30)),
+            Op::Insert(TestOrder::new("s4", false, 101, 5)),
+        ]);
+        ob.apply(instructions).unwrap();
+        let (_, instructions) = ob.eval(vec![Op::Delete(String::from("s1"))]);
+        ob.apply(instructions).unwrap();
+
+        let mut fork = ob.clone();
+        let ids = |ob: &OrderBook<TestOrder>| -> Vec<String> {
+            ob.ask_orders().map(|o| o.order.id().clone()).collect()
+        };
+        assert_eq!(ids(&fork), ["s2", "s3", "s4"]);
+        assert_eq!(fork.queue_position(&String::from("s3")), Some((1, 20)));
+        assert_eq!(fork.book_hash(), ob.book_hash());
+
+        // Trading the fork leaves the original as it was
+        let (_, instructions) = fork.eval(vec![Op::Insert(TestOrder::new("b1", true, 100, 25))]);
+        fork.apply(instructions).unwrap();
+        assert_eq!(ids(&fork), ["s3", "s4"]);
+        assert_eq!(fork.order(&String::from("s3")).unwrap().remaining(), 25);
+        assert_eq!(ids(&ob), ["s2", "s3", "s4"]);
+        assert_eq!(ob.order(&String::from("s3")).unwrap().remaining(), 30);
+        assert_ne!(fork.book_hash(), ob.book_hash());
+
+        // The fork can move to another thread and back
+        let fork = std::thread::spawn(move || fork).join().unwrap();
+        assert_eq!(fork.best_ask(), Some((100, 25)));
+    }
+
     #[test]
     fn test_order_lookup() {
         let mut ob = OrderBook::<TestOrder>::default();
@@ -423,6 +1576,43 @@ mod tests {
         assert!(ob.order(&String::from("nonexistent")).is_none());
     }
 
+    #[test]
+    fn test_queue_position() {
+        // Walked, tracked from the start and tracked once orders rest
+        for track in [None, Some(true), Some(false)] {
+            let mut ob = OrderBook::<TestOrder>::default();
+            if track == Some(true) {
+                ob.track_queue();
+            }
+            setup_order(&mut ob, "b1", true, 100, 50);
+            setup_order(&mut ob, "b2", true, 100, 30);
+            setup_order(&mut ob, "b3", true, 100, 20);
+            setup_order(&mut ob, "b4", true, 90, 40);
+            if track == Some(false) {
+                ob.track_queue();
+            }
+
+            assert_eq!(ob.queue_position(&String::from("b1")), Some((0, 0)));
+            assert_eq!(ob.queue_position(&String::from("b3")), Some((2, 80)));
+            assert_eq!(ob.queue_position(&String::from("b4")), Some((0, 0)));
+            assert!(ob.queue_position(&String::from("x")).is_none());
+
+            ob.apply(vec![Instruction::Fill(String::from("b1"), 20)])
+                .unwrap();
+            assert_eq!(ob.queue_position(&String::from("b3")), Some((2, 60)));
+            ob.apply(vec![Instruction::Delete(String::from("b2"))])
+                .unwrap();
+            assert_eq!(ob.queue_position(&String::from("b3")), Some((1, 30)));
+
+            // A level emptied and created again keeps tracking
+            setup_order(&mut ob, "b5", true, 90, 10);
+            ob.apply(vec![Instruction::Delete(String::from("b4"))])
+                .unwrap();
+            setup_order(&mut ob, "b6", true, 90, 10);
+            assert_eq!(ob.queue_position(&String::from("b6")), Some((1, 10)));
+        }
+    }
+
     #[test]
     fn test_bids_asks_iterators() {
         let mut ob = OrderBook::<TestOrder>::default();
@@ -440,6 +1630,91 @@ mod tests {
         assert_eq!(ask_prices, vec![110, 120]);
     }
 
+    #[test]
+    fn test_checksum() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert_eq!(ob.checksum(25), Crc32::checksum(b""));
+
+        setup_order(&mut ob, "b1", true, 100, 50);
+        setup_order(&mut ob, "b2", true, 90, 30);
+        setup_order(&mut ob, "b3", true, 80, 10);
+        setup_order(&mut ob, "s1", false, 110, 40);
+        assert_eq!(
+            ob.checksum(25),
+            Crc32::checksum(b"100:50:110:40:90:30:80:10")
+        );
+        assert_eq!(ob.checksum(1), Crc32::checksum(b"100:50:110:40"));
+        assert_eq!(
+            ob.l3_checksum(),
+            Crc32::checksum(b"b1:100:50:b2:90:30:b3:80:10:s1:110:40")
+        );
+    }
+
+    #[test]
+    fn test_book_hash() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        assert!(ob.book_hash().is_none());
+        setup_order(&mut ob, "s1", false, 1000, 100);
+        ob.track_hash();
+        assert_eq!(ob.book_hash(), Some(ob.compute_hash()));
+
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("s2", false, 1000, 50))]);
+        ob.apply(instructions).unwrap();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("b1", true, 1000, 120)),
+            Op::Insert(TestOrder::new("b2", true, 990, 20)),
+        ]);
+        ob.apply(instructions).unwrap();
+        assert_eq!(ob.book_hash(), Some(ob.compute_hash()));
+
+        ob.apply(vec![Instruction::Delete(String::from("b2"))])
+            .unwrap();
+        assert_eq!(ob.book_hash(), Some(ob.compute_hash()));
+
+        ob.apply(vec![Instruction::Fill(String::from("s2"), 30)])
+            .unwrap();
+        assert_eq!(ob.book_hash(), Some(ob.compute_hash()));
+        assert!(ob.is_empty());
+        assert_eq!(ob.book_hash(), Some(0));
+
+        // Same state reached differently hashes the same
+        let mut other = OrderBook::<TestOrder>::default();
+        other
+            .apply(vec![Instruction::Insert(
+                TestOrder::new("x", true, 10, 10),
+                10,
+            )])
+            .unwrap();
+        setup_order(&mut ob, "x", true, 10, 10);
+        assert_eq!(other.compute_hash(), ob.compute_hash());
+    }
+
+    #[test]
+    fn test_estimate() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "s1", false, 1000, 50);
+        setup_order(&mut ob, "s2", false, 1010, 50);
+        setup_order(&mut ob, "b1", true, 990, 40);
+
+        let e = ob.estimate_market(true, 80);
+        assert_eq!(e.filled, 80);
+        assert_eq!(e.worst_price, Some(1010));
+        assert_eq!(e.levels, 2);
+        assert_eq!(e.average_price(), Some(1003)); // 80_300 / 80, truncated
+
+        let e = ob.estimate_limit(true, 80, 1005);
+        assert_eq!(e.filled, 50);
+        assert_eq!(e.levels, 1);
+
+        let e = ob.estimate_market(false, 100);
+        assert_eq!(e.filled, 40);
+        assert_eq!(e.notional, 39_600);
+
+        // Matches what eval would fill, without touching temp
+        let (m, _) = ob.eval_insert(TestOrder::new("b2", true, 1005, 80));
+        assert_eq!(m.unwrap().taker.1, ob.estimate_limit(true, 80, 1005).filled);
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Eval Tests
     // ─────────────────────────────────────────────────────────────────────────
@@ -591,7 +1866,8 @@ mod tests {
         ob.apply(vec![Instruction::Insert(
             TestOrder::new("1", true, 1000, 100),
             100,
-        )]);
+        )])
+        .unwrap();
         assert!(ob.orders.contains_key("1"));
         assert!(ob.temp.is_empty());
 
@@ -599,14 +1875,16 @@ mod tests {
         ob.apply(vec![Instruction::Insert(
             TestOrder::new("1", false, 1000, 100),
             100,
-        )]);
+        )])
+        .unwrap();
         assert!(!ob.asks.is_empty());
 
         let mut ob = OrderBook::<TestOrder>::default();
         ob.apply(vec![Instruction::Insert(
             TestOrder::new("1", true, 1000, 100),
             70,
-        )]);
+        )])
+        .unwrap();
         assert_eq!(ob.order(&String::from("1")).unwrap().remaining(), 70);
     }
 
@@ -614,17 +1892,25 @@ mod tests {
     fn test_apply_delete() {
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", true, 1000, 100);
-        ob.apply(vec![Instruction::Delete(String::from("1"))]);
+        ob.apply(vec![Instruction::Delete(String::from("1"))])
+            .unwrap();
         assert!(ob.bids.is_empty());
 
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", false, 1000, 100);
-        ob.apply(vec![Instruction::Delete(String::from("1"))]);
+        ob.apply(vec![Instruction::Delete(String::from("1"))])
+            .unwrap();
         assert!(ob.asks.is_empty());
 
-        // Non-existent (no panic)
+        // Non-existent
         let mut ob = OrderBook::<TestOrder>::default();
-        ob.apply(vec![Instruction::Delete(String::from("x"))]);
+        assert_eq!(
+            ob.apply(vec![Instruction::Delete(String::from("x"))]),
+            Err(ApplyError::UnknownOrder {
+                index: 0,
+                id: String::from("x")
+            })
+        );
     }
 
     #[test]
@@ -632,30 +1918,211 @@ mod tests {
         // Partial fill sell
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", false, 1000, 100);
-        ob.apply(vec![Instruction::Fill(String::from("1"), 30)]);
+        ob.apply(vec![Instruction::Fill(String::from("1"), 30)])
+            .unwrap();
         assert_eq!(ob.order(&String::from("1")).unwrap().remaining(), 70);
 
         // Complete fill sell
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", false, 1000, 100);
-        ob.apply(vec![Instruction::Fill(String::from("1"), 100)]);
+        ob.apply(vec![Instruction::Fill(String::from("1"), 100)])
+            .unwrap();
         assert!(ob.asks.is_empty());
 
         // Partial fill buy
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", true, 1000, 100);
-        ob.apply(vec![Instruction::Fill(String::from("1"), 30)]);
+        ob.apply(vec![Instruction::Fill(String::from("1"), 30)])
+            .unwrap();
         assert_eq!(ob.order(&String::from("1")).unwrap().remaining(), 70);
 
         // Complete fill buy
         let mut ob = OrderBook::<TestOrder>::default();
         setup_order(&mut ob, "1", true, 1000, 100);
-        ob.apply(vec![Instruction::Fill(String::from("1"), 100)]);
+        ob.apply(vec![Instruction::Fill(String::from("1"), 100)])
+            .unwrap();
         assert!(ob.bids.is_empty());
 
-        // Non-existent (no panic)
+        // Non-existent
         let mut ob = OrderBook::<TestOrder>::default();
-        ob.apply(vec![Instruction::Fill(String::from("x"), 50)]);
+        assert_eq!(
+            ob.apply(vec![Instruction::Fill(String::from("x"), 50)]),
+            Err(ApplyError::UnknownOrder {
+                index: 0,
+                id: String::from("x")
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "s1", false, 1000, 100);
+        setup_order(&mut ob, "s2", false, 1010, 50);
+
+        // Instructions before the invalid one stay applied, later ones do not
+        let err = ob.apply(vec![
+            Instruction::Fill(String::from("s1"), 40),
+            Instruction::Fill(String::from("s1"), 70),
+            Instruction::Delete(String::from("s2")),
+        ]);
+        assert_eq!(
+            err,
+            Err(ApplyError::Overfill {
+                index: 1,
+                id: String::from("s1"),
+                quantity: 70,
+                remaining: 60
+            })
+        );
+        assert_eq!(ob.order(&String::from("s1")).unwrap().remaining(), 60);
+        assert!(ob.order(&String::from("s2")).is_some());
+
+        let err = ob.apply_strict(vec![Instruction::Fill(String::from("s2"), 10)]);
+        assert_eq!(
+            err,
+            Err(ApplyError::PriceMismatch {
+                index: 0,
+                id: String::from("s2"),
+                price: 1010,
+                best: 1000
+            })
+        );
+
+        let err = ob.apply(vec![Instruction::Insert(
+            TestOrder::new("s1", false, 1020, 10),
+            10,
+        )]);
+        assert_eq!(
+            err,
+            Err(ApplyError::DuplicateOrder {
+                index: 0,
+                id: String::from("s1")
+            })
+        );
+
+        let err = ob.apply(vec![Instruction::Insert(
+            TestOrder::new("s3", false, 1020, 10),
+            20,
+        )]);
+        assert_eq!(err.unwrap_err().index(), 0);
+        assert_eq!(ob.len(), 2);
+
+        // An order filled before it arrives rests at most what it has left
+        let mut order = TestOrder::new("b1", true, 990, 20);
+        order.fill(5);
+        let err = ob.apply(vec![Instruction::Insert(order.clone(), 16)]);
+        assert_eq!(
+            err,
+            Err(ApplyError::Overfill {
+                index: 0,
+                id: String::from("b1"),
+                quantity: 16,
+                remaining: 15
+            })
+        );
+        ob.apply(vec![Instruction::Insert(order, 15)]).unwrap();
+        assert_eq!(ob.order(&String::from("b1")).unwrap().remaining(), 15);
+
+        // Zero quantities are rejected rather than resting or recording empty trades
+        let zero = |index| ApplyError::ZeroQuantity {
+            index,
+            id: String::from("s1"),
+        };
+        let err = ob.apply(vec![Instruction::Fill(String::from("s1"), 0)]);
+        assert_eq!(err, Err(zero(0)));
+        let err = ob.apply(vec![
+            Instruction::Reduce(String::from("s1"), 10),
+            Instruction::Reduce(String::from("s1"), 0),
+        ]);
+        assert_eq!(err, Err(zero(1)));
+        assert_eq!(ob.order(&String::from("s1")).unwrap().remaining(), 50);
+        ob.apply(vec![Instruction::Delete(String::from("s1"))])
+            .unwrap();
+        let err = ob.apply(vec![Instruction::Insert(
+            TestOrder::new("s1", false, 1000, 10),
+            0,
+        )]);
+        assert_eq!(err, Err(zero(0)));
+        assert!(ob.order(&String::from("s1")).is_none());
+    }
+
+    #[test]
+    fn test_apply_fill_stale_handle() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "s1", false, 1000, 100);
+        // Leave the id mapped to a handle whose slot is gone
+        let (_, handle) = ob.orders[&String::from("s1")];
+        ob.asks.remove_order(handle);
+        for instruction in [
+            Instruction::Fill(String::from("s1"), 10),
+            Instruction::Reduce(String::from("s1"), 10),
+        ] {
+            assert_eq!(
+                ob.apply(vec![instruction]),
+                Err(ApplyError::UnknownOrder {
+                    index: 0,
+                    id: String::from("s1")
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_reduce_and_strict() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.enable_tape(4);
+        setup_order(&mut ob, "s1", false, 1000, 100);
+        setup_order(&mut ob, "s2", false, 1000, 50);
+        setup_order(&mut ob, "s3", false, 1010, 50);
+
+        let mut feed = OrderFeed::default();
+        ob.apply_with_events(vec![Instruction::Reduce(String::from("s1"), 30)], &mut feed)
+            .unwrap();
+        assert_eq!(
+            feed.events,
+            vec![OrderEvent::Reduce {
+                sequence: 1,
+                id: String::from("s1"),
+                quantity: 30,
+                remaining: 70
+            }]
+        );
+        // Keeps its place at the front of the level and does not trade
+        assert_eq!(ob.queue_position(&String::from("s1")), Some((0, 0)));
+        assert_eq!(ob.tape().unwrap().stats().count, 0);
+
+        // A fill behind the best price is only rejected when strict
+        let fill = || vec![Instruction::Fill(String::from("s3"), 50)];
+        assert!(ob.apply_strict(fill()).is_err());
+        ob.apply(fill()).unwrap();
+        assert!(ob.order(&String::from("s3")).is_none());
+        assert_eq!(ob.tape().unwrap().stats().volume, 50);
+    }
+
+    #[test]
+    fn test_apply_multi_op_eval() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "a", false, 100, 5);
+
+        // s1 rests at 99 before b1 fills a at 100, since eval does not match
+        // within a batch
+        let ops = || {
+            vec![
+                Op::Insert(TestOrder::new("s1", false, 99, 5)),
+                Op::Insert(TestOrder::new("b1", true, 100, 5)),
+            ]
+        };
+        let (_, instructions) = ob.clone().eval(ops());
+        assert!(ob.clone().apply_strict(instructions.clone()).is_err());
+        ob.clone().apply(instructions.clone()).unwrap();
+        ob.clone().apply_atomic(instructions).unwrap();
+
+        let (_, batch) = ob.eval_batch(ops());
+        assert_eq!(ob.apply_batch(batch), Ok(1));
+        assert!(ob.order(&String::from("a")).is_none());
+        assert_eq!(ob.best_ask(), Some((99, 5)));
+        assert!(ob.best_bid().is_none());
     }
 
     #[test]
@@ -664,15 +2131,238 @@ mod tests {
         ob.apply(vec![
             Instruction::NoOp(Msg::OrderNotFound),
             Instruction::NoOp(Msg::OrderAlreadyExists),
-        ]);
+        ])
+        .unwrap();
         assert!(ob.bids.is_empty());
     }
 
+    #[test]
+    fn test_apply_with_updates() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        let mut updates = Vec::new();
+        ob.apply_with_updates(
+            vec![
+                Instruction::Insert(TestOrder::new("s1", false, 1000, 100), 100),
+                Instruction::Insert(TestOrder::new("s2", false, 1000, 50), 50),
+                Instruction::Insert(TestOrder::new("s3", false, 1010, 20), 20),
+            ],
+            &mut updates,
+        )
+        .unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                LevelUpdate::Add {
+                    is_bid: false,
+                    price: 1000,
+                    quantity: 100,
+                    orders: 1
+                },
+                LevelUpdate::Change {
+                    is_bid: false,
+                    price: 1000,
+                    quantity: 150,
+                    orders: 2
+                },
+                LevelUpdate::Add {
+                    is_bid: false,
+                    price: 1010,
+                    quantity: 20,
+                    orders: 1
+                },
+            ]
+        );
+
+        updates.clear();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("b1", true, 1000, 120)),
+            Op::Delete(String::from("s3")),
+        ]);
+        ob.apply_with_updates(instructions, &mut updates).unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                LevelUpdate::Change {
+                    is_bid: false,
+                    price: 1000,
+                    quantity: 50,
+                    orders: 1
+                },
+                LevelUpdate::Change {
+                    is_bid: false,
+                    price: 1000,
+                    quantity: 30,
+                    orders: 1
+                },
+                LevelUpdate::Remove {
+                    is_bid: false,
+                    price: 1010
+                },
+            ]
+        );
+
+        updates.clear();
+        ob.apply_with_updates(
+            vec![Instruction::Fill(String::from("s2"), 30)],
+            &mut updates,
+        )
+        .unwrap();
+        assert_eq!(
+            updates,
+            vec![LevelUpdate::Remove {
+                is_bid: false,
+                price: 1000
+            }]
+        );
+        assert!(ob.is_empty());
+    }
+
+    #[test]
+    fn test_apply_with_events() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        let mut feed = OrderFeed::default();
+        ob.apply_with_events(
+            vec![
+                Instruction::Insert(TestOrder::new("s1", false, 1000, 100), 100),
+                Instruction::Insert(TestOrder::new("s2", false, 1010, 50), 50),
+            ],
+            &mut feed,
+        )
+        .unwrap();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("b1", true, 1000, 150)),
+            Op::Delete(String::from("s2")),
+        ]);
+        ob.apply_with_events(instructions, &mut feed).unwrap();
+
+        let id = String::from;
+        assert_eq!(
+            feed.events,
+            vec![
+                OrderEvent::Add {
+                    sequence: 1,
+                    id: id("s1"),
+                    is_buy: false,
+                    price: 1000,
+                    quantity: 100
+                },
+                OrderEvent::Add {
+                    sequence: 2,
+                    id: id("s2"),
+                    is_buy: false,
+                    price: 1010,
+                    quantity: 50
+                },
+                OrderEvent::Add {
+                    sequence: 3,
+                    id: id("b1"),
+                    is_buy: true,
+                    price: 1000,
+                    quantity: 50
+                },
+                OrderEvent::Execute {
+                    sequence: 4,
+                    id: id("s1"),
+                    price: 1000,
+                    quantity: 100,
+                    remaining: 0
+                },
+                OrderEvent::Delete {
+                    sequence: 5,
+                    id: id("s2")
+                },
+            ]
+        );
+        assert_eq!(feed.sequence(), 5);
+
+        // Sequence continues from a given start
+        let mut feed = OrderFeed::new(41);
+        ob.apply_with_events(
+            vec![
+                Instruction::Reduce(id("b1"), 20),
+                Instruction::Delete(id("b1")),
+            ],
+            &mut feed,
+        )
+        .unwrap();
+        assert_eq!(
+            feed.events[0],
+            OrderEvent::Reduce {
+                sequence: 42,
+                id: id("b1"),
+                quantity: 20,
+                remaining: 30
+            }
+        );
+        assert_eq!(feed.events[1].sequence(), 43);
+    }
+
+    #[test]
+    fn test_apply_records_trades() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "s1", false, 1000, 50);
+        setup_order(&mut ob, "s2", false, 1010, 50);
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("b1", true, 1010, 60))]);
+        ob.apply(instructions).unwrap();
+        assert!(ob.tape().is_none());
+
+        ob.enable_tape(10);
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("b2", true, 1010, 30))]);
+        ob.apply(instructions).unwrap();
+        ob.apply(vec![Instruction::Fill(String::from("s2"), 5)])
+            .unwrap();
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("b3", true, 1005, 15))]);
+        ob.apply(instructions).unwrap();
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("s3", false, 900, 5))]);
+        ob.apply(instructions).unwrap();
+
+        let tape = ob.tape().unwrap();
+        let trades: Vec<(&str, bool, u64, u64)> = tape
+            .trades()
+            .map(|t| (t.maker.as_str(), t.is_buy, t.price, t.quantity))
+            .collect();
+        assert_eq!(
+            trades,
+            vec![
+                ("s2", true, 1010, 30),
+                ("s2", true, 1010, 5),
+                ("b3", false, 1005, 5)
+            ]
+        );
+        assert_eq!(tape.stats().volume, 40);
+        assert_eq!(tape.stats().count, 3);
+        assert_eq!(tape.stats().low, Some(1005));
+        assert_eq!(tape.stats().vwap(), Some(1009));
+
+        ob.tape_mut().unwrap().reset();
+        assert_eq!(ob.tape().unwrap().stats().count, 0);
+    }
+
+    #[test]
+    fn test_apply_with_bars() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        setup_order(&mut ob, "s1", false, 1000, 50);
+        setup_order(&mut ob, "s2", false, 1010, 50);
+        let mut bars = BarAggregator::new(BarKind::Ticks(2), || 7);
+
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("b1", true, 1010, 60))]);
+        ob.apply_with_bars(instructions, &mut bars).unwrap();
+        ob.apply_with_bars(vec![Instruction::Fill(String::from("s2"), 5)], &mut bars)
+            .unwrap();
+
+        let bar = bars.completed()[0];
+        assert_eq!(
+            (bar.open, bar.close, bar.volume, bar.end),
+            (1000, 1010, 60, 7)
+        );
+        assert_eq!(bars.current().unwrap().volume, 5);
+    }
+
     #[test]
     fn test_apply_clears_temp() {
         let mut ob = OrderBook::<TestOrder>::default();
         ob.temp.insert(String::from("1"), 50);
-        ob.apply(vec![]);
+        ob.apply(vec![]).unwrap();
         assert!(ob.temp.is_empty());
     }
 
@@ -687,7 +2377,7 @@ mod tests {
         let ops = vec![Op::Insert(TestOrder::new("b1", true, 1000, 60))];
         let (matches, instructions) = ob.eval(ops);
         assert_eq!(matches[0].taker.1, 60);
-        ob.apply(instructions);
+        ob.apply(instructions).unwrap();
         assert_eq!(ob.order(&String::from("s1")).unwrap().remaining(), 40);
         assert!(!ob.orders.contains_key("b1"));
     }
@@ -698,8 +2388,97 @@ mod tests {
         setup_order(&mut ob, "s1", false, 1000, 50);
         let ops = vec![Op::Insert(TestOrder::new("b1", true, 1000, 100))];
         let (_, instructions) = ob.eval(ops);
-        ob.apply(instructions);
+        ob.apply(instructions).unwrap();
         assert!(ob.asks.is_empty());
         assert_eq!(ob.order(&String::from("b1")).unwrap().remaining(), 50);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut ob = OrderBook::<TestOrder>::default();
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("s1", false, 100, 50))]);
+        ob.apply(instructions).unwrap();
+
+        let ops = vec![
+            Op::Insert(TestOrder::new("b1", true, 100, 20)),
+            Op::Delete(String::from("s9")),
+        ];
+        let json = serde_json::to_string(&ops).unwrap();
+        let ops: Vec<Op<TestOrder>> = serde_json::from_str(&json).unwrap();
+        let (matches, instructions) = ob.eval(ops);
+
+        let json = serde_json::to_string(&instructions).unwrap();
+        let decoded: Vec<Instruction<TestOrder>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, instructions);
+        assert_eq!(decoded[1], Instruction::NoOp(Msg::OrderNotFound));
+
+        let json = serde_json::to_string(&matches[0]).unwrap();
+        let m: Match<TestOrder> = serde_json::from_str(&json).unwrap();
+        assert_eq!(m.taker, (String::from("b1"), 20));
+        assert_eq!(m.makers, vec![(String::from("s1"), 20)]);
+
+        let mut depth = Depth::default();
+        ob.depth(5, &mut depth);
+        let json = serde_json::to_string(&depth).unwrap();
+        let decoded: Depth<TestOrder> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.asks, depth.asks);
+        assert!(decoded.bids.is_empty());
+    }
+
+    #[test]
+    fn test_apply_batch_sequence() {
+        let mut leader = OrderBook::<TestOrder>::default();
+        let mut follower = OrderBook::<TestOrder>::default();
+        let mut batches = Vec::new();
+        for (id, price) in [("s1", 100), ("s2", 101), ("s3", 102)] {
+            let (_, batch) =
+                leader.eval_batch(vec![Op::Insert(TestOrder::new(id, false, price, 10))]);
+            let replica = Batch {
+                sequence: batch.sequence,
+                instructions: vec![Instruction::Insert(
+                    TestOrder::new(id, false, price, 10),
+                    10,
+                )],
+            };
+            assert_eq!(batch.sequence, leader.sequence() + 1);
+            assert_eq!(leader.apply_batch(batch), Ok(replica.sequence));
+            batches.push(replica);
+        }
+        assert_eq!(leader.sequence(), 3);
+
+        let mut batches = batches.into_iter();
+        let (first, second, third) = (
+            batches.next().unwrap(),
+            batches.next().unwrap(),
+            batches.next().unwrap(),
+        );
+        assert_eq!(
+            follower.apply_batch(second),
+            Err(BatchError::Sequence(SequenceError::Gap {
+                sequence: 2,
+                last: 0
+            }))
+        );
+        assert!(follower.is_empty());
+        assert_eq!(follower.check_sequence(1), Ok(()));
+        assert_eq!(follower.apply_batch(first), Ok(1));
+
+        let duplicate = Batch {
+            sequence: 1,
+            instructions: vec![Instruction::Delete(String::from("s1"))],
+        };
+        assert_eq!(
+            follower.apply_batch(duplicate),
+            Err(BatchError::Sequence(SequenceError::Duplicate {
+                sequence: 1,
+                last: 1
+            }))
+        );
+        assert!(follower.order(&String::from("s1")).is_some());
+
+        follower.set_sequence(2);
+        assert_eq!(follower.apply_batch(third), Ok(3));
+        assert_eq!(follower.len(), 2);
+    }
 }
@@ -39,6 +39,7 @@ pub trait OrderInterface {
 
 #[cfg(test)]
 #[derive(Default, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TestOrder {
     id: String,
     is_buy: bool,
@@ -89,3 +90,79 @@ impl OrderInterface for TestOrder {
         self.remaining -= quantity;
     }
 }
+
+/// Snapshot codec for tests: `id len | id | is_buy | price | quantity | remaining`.
+#[cfg(test)]
+pub struct TestCodec;
+
+#[cfg(test)]
+impl crate::snapshot::OrderCodec<TestOrder> for TestCodec {
+    fn encode(&self, order: &TestOrder, out: &mut Vec<u8>) {
+        out.push(order.id().len() as u8);
+        out.extend_from_slice(order.id().as_bytes());
+        out.push(order.is_buy() as u8);
+        for n in [order.price(), order.quantity(), order.remaining()] {
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<TestOrder> {
+        let (&len, rest) = bytes.split_first()?;
+        let (id, rest) = rest.split_at_checked(len as usize)?;
+        let (&is_buy, rest) = rest.split_first()?;
+        let n = |i: usize| {
+            Some(u64::from_le_bytes(
+                rest.get(i * 8..i * 8 + 8)?.try_into().ok()?,
+            ))
+        };
+        let mut order = TestOrder::new(std::str::from_utf8(id).ok()?, is_buy == 1, n(0)?, n(1)?);
+        order.fill(n(1)? - n(2)?);
+        Some(order)
+    }
+}
+
+#[cfg(test)]
+impl crate::journal::InstructionCodec<TestOrder> for TestCodec {
+    fn encode_id(&self, id: &String, out: &mut Vec<u8>) {
+        out.extend_from_slice(id.as_bytes());
+    }
+
+    fn decode_id(&self, bytes: &[u8]) -> Option<String> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    fn encode_quantity(&self, quantity: u64, out: &mut Vec<u8>) {
+        out.extend_from_slice(&quantity.to_le_bytes());
+    }
+
+    fn decode_quantity(&self, bytes: &[u8]) -> Option<u64> {
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+#[cfg(test)]
+impl crate::fix::FixCodec<TestOrder> for TestCodec {
+    fn decode_id(&self, value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    fn decode_price(&self, value: &str) -> Option<u64> {
+        value.parse().ok()
+    }
+
+    fn decode_quantity(&self, value: &str) -> Option<u64> {
+        value.parse().ok()
+    }
+
+    fn encode_price(&self, price: u64) -> String {
+        price.to_string()
+    }
+
+    fn encode_quantity(&self, quantity: u64) -> String {
+        quantity.to_string()
+    }
+
+    fn order(&self, id: String, is_buy: bool, price: u64, quantity: u64) -> TestOrder {
+        TestOrder::new(&id, is_buy, price, quantity)
+    }
+}
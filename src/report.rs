@@ -0,0 +1,237 @@
+use crate::{
+    hash::FxHashMap,
+    ob::{Instruction, Match, Msg, OrderBook},
+    order::OrderInterface,
+};
+
+/// A taker as (id, cumulative, leaves, fills left to report).
+type Taker<'a, O> = (
+    &'a <O as OrderInterface>::T,
+    <O as OrderInterface>::N,
+    <O as OrderInterface>::N,
+    usize,
+);
+
+/// An order's state as of an execution report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected(Msg),
+}
+
+/// One step in an order's lifecycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecReport<O: OrderInterface> {
+    /// None for rejections: `eval` does not say which op it rejected, but
+    /// reports come out in op order.
+    pub id: Option<O::T>,
+    pub status: ExecStatus,
+    /// (price, quantity) of the fill this report is for, if any.
+    pub fill: Option<(O::N, O::N)>,
+    /// Quantity executed so far.
+    pub cumulative: O::N,
+    /// Quantity still open.
+    pub leaves: O::N,
+}
+
+impl<O: OrderInterface> ExecReport<O> {
+    #[inline]
+    fn new(id: &O::T, status: ExecStatus, cumulative: O::N, leaves: O::N) -> Self {
+        Self {
+            id: Some(id.clone()),
+            status,
+            fill: None,
+            cumulative,
+            leaves,
+        }
+    }
+
+    #[inline]
+    fn fill(id: &O::T, price: O::N, quantity: O::N, cumulative: O::N, leaves: O::N) -> Self {
+        let status = if leaves == O::N::default() {
+            ExecStatus::Filled
+        } else {
+            ExecStatus::PartiallyFilled
+        };
+        Self {
+            fill: Some((price, quantity)),
+            ..Self::new(id, status, cumulative, leaves)
+        }
+    }
+}
+
+impl<O: OrderInterface> OrderBook<O> {
+    /// Builds execution reports from what `eval` returned, in instruction order:
+    /// a `New` report for each taker, then per fill one report for the taker and
+    /// one for the maker, `Cancelled` for deletes and `Rejected` for no-ops.
+    ///
+    /// Fill prices and maker state are read from the book, so call this before
+    /// applying the instructions.
+    pub fn reports(
+        &self,
+        matches: &[Match<O>],
+        instructions: &[Instruction<O>],
+    ) -> Vec<ExecReport<O>> {
+        let zero = O::N::default();
+        let mut reports = Vec::with_capacity(instructions.len() * 2);
+        let mut matches = matches.iter();
+        // Quantity taken from resting orders by earlier instructions
+        let mut taken: FxHashMap<&O::T, O::N> = FxHashMap::default();
+        // The taker of the next fills
+        let mut taker: Option<Taker<O>> = None;
+
+        for instruction in instructions {
+            match instruction {
+                Instruction::Insert(order, remaining) => {
+                    let (cumulative, leaves) =
+                        (order.quantity() - order.remaining(), order.remaining());
+                    reports.push(ExecReport::new(
+                        order.id(),
+                        ExecStatus::New,
+                        cumulative,
+                        leaves,
+                    ));
+                    if *remaining < leaves
+                        && let Some(m) = matches.next()
+                    {
+                        taker = Some((order.id(), cumulative, leaves, m.makers.len()));
+                    }
+                }
+                Instruction::Fill(id, quantity) => {
+                    // A taker filled in full is never inserted
+                    if taker.is_none_or(|(.., left)| left == 0)
+                        && let Some(m) = matches.next()
+                    {
+                        let (id, filled) = (&m.taker.0, m.taker.1);
+                        reports.push(ExecReport::new(id, ExecStatus::New, zero, filled));
+                        taker = Some((id, zero, filled, m.makers.len()));
+                    }
+                    let Some(maker) = self.order(id) else {
+                        continue;
+                    };
+                    let price = maker.price();
+                    if let Some((taker_id, cumulative, leaves, left)) = &mut taker {
+                        *cumulative += *quantity;
+                        *leaves -= *quantity;
+                        *left -= 1;
+                        let report =
+                            ExecReport::fill(*taker_id, price, *quantity, *cumulative, *leaves);
+                        reports.push(report);
+                    }
+                    let taken = taken.entry(id).or_default();
+                    *taken += *quantity;
+                    let leaves = maker.remaining() - *taken;
+                    let cumulative = maker.quantity() - leaves;
+                    reports.push(ExecReport::fill(id, price, *quantity, cumulative, leaves));
+                }
+                Instruction::Delete(id) => {
+                    let Some(order) = self.order(id) else {
+                        continue;
+                    };
+                    let taken = taken.get(id).copied().unwrap_or_default();
+                    let cumulative = order.quantity() - order.remaining() + taken;
+                    reports.push(ExecReport::new(id, ExecStatus::Cancelled, cumulative, zero));
+                }
+                // Never produced by eval
+                Instruction::Reduce(..) => {}
+                Instruction::NoOp(msg) => reports.push(ExecReport {
+                    id: None,
+                    status: ExecStatus::Rejected(*msg),
+                    fill: None,
+                    cumulative: zero,
+                    leaves: zero,
+                }),
+            }
+        }
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ob::Op, order::TestOrder};
+
+    /// A report for `id`, or for no order if it is empty.
+    fn report(
+        id: &str,
+        status: ExecStatus,
+        fill: Option<(u64, u64)>,
+        cumulative: u64,
+        leaves: u64,
+    ) -> ExecReport<TestOrder> {
+        ExecReport {
+            id: (!id.is_empty()).then(|| String::from(id)),
+            status,
+            fill,
+            cumulative,
+            leaves,
+        }
+    }
+
+    #[test]
+    fn test_reports_taker_and_makers() {
+        let mut ob = OrderBook::default();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 100, 10)),
+            Op::Insert(TestOrder::new("s2", false, 101, 10)),
+        ]);
+        ob.apply(instructions).unwrap();
+
+        let (matches, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("b1", true, 101, 15)),
+            Op::Insert(TestOrder::new("b2", true, 101, 8)),
+        ]);
+        let reports = ob.reports(&matches, &instructions);
+        use ExecStatus::*;
+        assert_eq!(
+            reports,
+            [
+                report("b1", New, None, 0, 15),
+                report("b1", PartiallyFilled, Some((100, 10)), 10, 5),
+                report("s1", Filled, Some((100, 10)), 10, 0),
+                report("b1", Filled, Some((101, 5)), 15, 0),
+                report("s2", PartiallyFilled, Some((101, 5)), 5, 5),
+                // b2 rests before its fills, with what it does not take
+                report("b2", New, None, 0, 8),
+                report("b2", PartiallyFilled, Some((101, 5)), 5, 3),
+                report("s2", Filled, Some((101, 5)), 10, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_cancel_and_reject() {
+        let mut ob = OrderBook::default();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 100, 10)),
+            Op::Insert(TestOrder::new("b1", true, 99, 10)),
+        ]);
+        ob.apply(instructions).unwrap();
+
+        let (matches, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("b2", true, 100, 4)),
+            Op::Delete(String::from("s1")),
+            Op::Delete(String::from("x")),
+            Op::Insert(TestOrder::new("b1", true, 98, 1)),
+            Op::Insert(TestOrder::new("b3", true, 97, 2)),
+        ]);
+        let reports = ob.reports(&matches, &instructions);
+        use ExecStatus::*;
+        assert_eq!(
+            reports,
+            [
+                report("b2", New, None, 0, 4),
+                report("b2", Filled, Some((100, 4)), 4, 0),
+                report("s1", PartiallyFilled, Some((100, 4)), 4, 6),
+                report("s1", Cancelled, None, 4, 0),
+                report("", Rejected(Msg::OrderNotFound), None, 0, 0),
+                report("", Rejected(Msg::OrderAlreadyExists), None, 0, 0),
+                report("b3", New, None, 0, 2),
+            ]
+        );
+    }
+}
@@ -1,47 +1,141 @@
-use crate::{hash::FxHashMap, level::Level, list::Node, order::OrderInterface};
-use std::collections::BTreeSet;
-
-/// One side of an orderbook (bids or asks). Uses BTreeMap for price-sorted levels.
-/// `is_bid` determines iteration direction at runtime.
+use crate::{
+    depth::{DepthLevel, DepthOrder},
+    estimate::FillEstimate,
+    hash::FxHashMap,
+    ladder::Ladder,
+    level::{Level, LevelRef},
+    list::{self, Handle, Slab},
+    order::OrderInterface,
+};
+use std::{
+    collections::BTreeSet,
+    ops::Bound::{Excluded, Unbounded},
+};
+
+/// One side of an orderbook (bids or asks). Uses BTreeMap for price-sorted levels,
+/// or a dense tick ladder if created with `with_ladder`, keeping only prices off
+/// the ladder in the tree. `is_bid` determines iteration direction at runtime.
 pub struct Side<O: OrderInterface> {
     is_bid: bool,
     prices: BTreeSet<O::N>,
     levels: FxHashMap<O::N, Level<O>>,
+    ladder: Option<Ladder<O>>,
+    /// Nodes for the orders in every level.
+    nodes: Slab<O>,
+    /// Whether levels keep queue sums, see `track_queue`.
+    track_queue: bool,
+}
+
+impl<O: OrderInterface + Clone> Clone for Side<O> {
+    fn clone(&self) -> Self {
+        Side {
+            is_bid: self.is_bid,
+            prices: self.prices.clone(),
+            levels: self.levels.clone(),
+            ladder: self.ladder.clone(),
+            nodes: self.nodes.clone(),
+            track_queue: self.track_queue,
+        }
+    }
 }
 
 impl<O: OrderInterface> Side<O> {
     #[inline]
     pub fn new(is_bid: bool) -> Self {
+        Self::with_capacity(is_bid, 0)
+    }
+
+    /// Creates a side that holds `capacity` orders before allocating order nodes.
+    #[inline]
+    pub fn with_capacity(is_bid: bool, capacity: usize) -> Self {
         Side {
             is_bid,
             prices: BTreeSet::new(),
             levels: FxHashMap::default(),
+            ladder: None,
+            nodes: Slab::with_capacity(capacity),
+            track_queue: false,
+        }
+    }
+
+    /// Creates a side keeping the levels for prices `min..=max` in steps of
+    /// `tick` in an array indexed by tick. Levels at other prices still work,
+    /// at the cost of the tree. Panics if `tick` is zero or `max < min`.
+    #[inline]
+    pub fn with_ladder(is_bid: bool, min: O::N, max: O::N, tick: O::N) -> Self
+    where
+        O::N: TryInto<usize>,
+    {
+        Side {
+            ladder: Some(Ladder::new(min, max, tick)),
+            ..Self::new(is_bid)
+        }
+    }
+
+    /// Returns how many orders the side holds before allocating order nodes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Makes room for at least `additional` more orders.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// Makes `queue_position` take O(log n) in every level, present and future,
+    /// at O(log n) extra per order added, filled or removed.
+    pub fn track_queue(&mut self) {
+        self.track_queue = true;
+        for level in self.levels.values_mut() {
+            level.track_queue(&mut self.nodes);
+        }
+        if let Some(ladder) = &mut self.ladder {
+            for level in ladder.levels_mut() {
+                level.track_queue(&mut self.nodes);
+            }
         }
     }
 
     #[inline]
     pub fn height(&self) -> usize {
-        self.levels.len()
+        self.levels.len() + self.ladder.as_ref().map_or(0, Ladder::len)
     }
 
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.levels.is_empty()
+        self.height() == 0
     }
 
     /// Returns the best price level (price, total_quantity).
     /// For bids: highest price. For asks: lowest price.
     #[inline]
     pub fn best(&self) -> Option<(O::N, O::N)> {
-        let price = if self.is_bid {
-            self.prices.last()
+        self.iter()
+            .next()
+            .map(|level| (level.price(), level.total_quantity()))
+    }
+
+    /// Returns the best price, without looking up its level.
+    #[inline(always)]
+    pub fn best_price(&self) -> Option<O::N> {
+        let sparse = if self.is_bid {
+            self.prices.last().copied()
         } else {
-            self.prices.first()
+            self.prices.first().copied()
+        };
+        let Some(ladder) = &self.ladder else {
+            return sparse;
         };
-        price.map(|p| {
-            let level = self.levels.get(p).unwrap();
-            (*p, level.total_quantity())
-        })
+        let dense = ladder
+            .best(self.is_bid)
+            .map(|i| ladder.get(i).unwrap().price());
+        match (sparse, dense) {
+            (Some(sparse), Some(dense)) if self.is_bid => Some(sparse.max(dense)),
+            (Some(sparse), Some(dense)) => Some(sparse.min(dense)),
+            (sparse, dense) => sparse.or(dense),
+        }
     }
 
     /// Returns the top `n` price levels as (price, total_quantity).
@@ -54,78 +148,232 @@ impl<O: OrderInterface> Side<O> {
             .collect()
     }
 
+    /// Fills `out` with the top `n` levels and their cumulative totals.
+    /// Clears `out` first; its capacity is reused.
+    #[inline]
+    pub fn depth(&self, n: usize, out: &mut Vec<DepthLevel<O>>) {
+        out.clear();
+        let mut cumulative_quantity = O::N::default();
+        let mut cumulative_notional = O::N::default();
+        for level in self.iter().take(n) {
+            let quantity = level.total_quantity();
+            cumulative_quantity += quantity;
+            cumulative_notional += level.price() * quantity;
+            out.push(DepthLevel {
+                price: level.price(),
+                quantity,
+                orders: level.len(),
+                cumulative_quantity,
+                cumulative_notional,
+            });
+        }
+    }
+
+    /// Walks levels best first as an incoming order of `quantity` would, stopping
+    /// at levels worse than `limit` (if any). Does not modify the side.
+    #[inline]
+    pub fn sweep(&self, quantity: O::N, limit: Option<O::N>) -> FillEstimate<O> {
+        let mut estimate = FillEstimate {
+            filled: O::N::default(),
+            notional: O::N::default(),
+            worst_price: None,
+            levels: 0,
+        };
+        for level in self.iter() {
+            let price = level.price();
+            let outside = match limit {
+                Some(limit) if self.is_bid => price < limit,
+                Some(limit) => price > limit,
+                None => false,
+            };
+            if outside || estimate.filled == quantity {
+                break;
+            }
+            let take = level.total_quantity().min(quantity - estimate.filled);
+            estimate.filled += take;
+            estimate.notional += price * take;
+            estimate.worst_price = Some(price);
+            estimate.levels += 1;
+        }
+        estimate
+    }
+
+    /// Returns the best level worse than `price`, looking it up rather than
+    /// walking the levels before it.
+    #[inline]
+    pub fn after(&self, price: O::N) -> Option<LevelRef<'_, O>> {
+        let sparse = if self.is_bid {
+            self.prices.range(..price).next_back()
+        } else {
+            self.prices.range((Excluded(price), Unbounded)).next()
+        };
+        let sparse = sparse.map(|p| &self.levels[p]);
+        let dense = self.ladder.as_ref().and_then(|ladder| {
+            let index = ladder.after_price(price, self.is_bid)?;
+            ladder.get(index)
+        });
+        let level = match (sparse, dense) {
+            (Some(sparse), Some(dense)) if self.is_bid => {
+                if dense.price() > sparse.price() {
+                    dense
+                } else {
+                    sparse
+                }
+            }
+            (Some(sparse), Some(dense)) => {
+                if dense.price() < sparse.price() {
+                    dense
+                } else {
+                    sparse
+                }
+            }
+            (sparse, dense) => sparse.or(dense)?,
+        };
+        Some(LevelRef::new(level, &self.nodes))
+    }
+
+    /// Returns the level at `price`, if any.
+    #[inline]
+    pub fn level(&self, price: O::N) -> Option<&Level<O>> {
+        if let Some(ladder) = &self.ladder
+            && let Some(index) = ladder.index(price)
+        {
+            return ladder.get(index);
+        }
+        self.levels.get(&price)
+    }
+
+    /// Rests an order at the back of its level and returns its handle.
     #[inline(always)]
-    pub fn insert_order(&mut self, order: O) -> *mut Node<O> {
+    pub fn insert_order(&mut self, order: O) -> Handle {
         let price = order.price();
+        if let Some(ladder) = &mut self.ladder
+            && let Some(index) = ladder.index(price)
+        {
+            let level = ladder.entry(index, price);
+            // Empty levels are dropped, so an empty one was just created
+            if self.track_queue && level.is_empty() {
+                level.track_queue(&mut self.nodes);
+            }
+            return level.add_order(&mut self.nodes, order);
+        }
         if let Some(level) = self.levels.get_mut(&price) {
-            level.add_order(order)
+            level.add_order(&mut self.nodes, order)
         } else {
             let mut level = Level::new(price);
-            let node_ptr = level.add_order(order);
+            if self.track_queue {
+                level.track_queue(&mut self.nodes);
+            }
+            let handle = level.add_order(&mut self.nodes, order);
             self.prices.insert(price);
             self.levels.insert(price, level);
-            node_ptr
+            handle
         }
     }
 
+    /// Returns the order behind a handle, or None if the handle is stale.
     #[inline(always)]
-    fn level_mut(&mut self, price: O::N) -> &mut Level<O> {
-        self.levels
+    pub fn order(&self, handle: Handle) -> Option<&O> {
+        self.nodes.get(handle)
+    }
+
+    /// Returns the order after `handle` in its level, or None if it is the last
+    /// or the handle is stale.
+    #[inline(always)]
+    pub fn next_order(&self, handle: Handle) -> Option<Handle> {
+        self.nodes.next(handle)
+    }
+
+    /// Returns (orders ahead, quantity ahead) of an order in its level, or None
+    /// if the handle is stale.
+    #[inline]
+    pub fn queue_position(&self, handle: Handle) -> Option<(usize, O::N)> {
+        let level = self.level(self.order(handle)?.price())?;
+        level.queue_position(&self.nodes, handle)
+    }
+
+    #[inline(always)]
+    fn level_mut(&mut self, price: O::N) -> (&mut Level<O>, &mut Slab<O>) {
+        if let Some(ladder) = &mut self.ladder
+            && let Some(index) = ladder.index(price)
+        {
+            let level = ladder.get_mut(index);
+            return (
+                level.expect("live order must rest in a level"),
+                &mut self.nodes,
+            );
+        }
+        let level = self
+            .levels
             .get_mut(&price)
-            .expect("node_ptr must point to valid order in this side")
+            .expect("live order must rest in a level");
+        (level, &mut self.nodes)
     }
 
     #[inline(always)]
     fn cleanup_level(&mut self, price: O::N, level_empty: bool) {
-        if level_empty {
+        if !level_empty {
+            return;
+        }
+        if let Some(ladder) = &mut self.ladder
+            && let Some(index) = ladder.index(price)
+        {
+            ladder.remove(index);
+        } else {
             self.prices.remove(&price);
             self.levels.remove(&price);
         }
     }
 
-    /// Fills an order and returns true if fully filled.
-    /// Caller must ensure node_ptr is valid and in this side.
+    /// Fills an order with at least `fill` left. Returns None if the handle is
+    /// stale, otherwise the order if it was fully filled (and removed).
     #[inline(always)]
-    #[allow(clippy::not_unsafe_ptr_arg_deref)]
-    pub fn fill_order(&mut self, node_ptr: *mut Node<O>, fill: O::N) -> bool {
-        let order = unsafe { &mut (*node_ptr).data };
-        let price = order.price();
-        let level = self.level_mut(price);
-        let removed = level.fill_order(node_ptr, order, fill);
+    pub(crate) fn fill_order(&mut self, handle: Handle, fill: O::N) -> Option<Option<O>> {
+        let price = self.order(handle)?.price();
+        let (level, nodes) = self.level_mut(price);
+        let removed = level.fill_order(nodes, handle, fill);
         let empty = level.is_empty();
         self.cleanup_level(price, empty);
         removed
     }
 
-    /// Removes an order by its node pointer.
-    /// Caller must ensure node_ptr is valid and in this side.
+    /// Removes an order and returns it, or None if the handle is stale.
     #[inline(always)]
-    #[allow(clippy::not_unsafe_ptr_arg_deref)]
-    pub fn remove_order(&mut self, node_ptr: *mut Node<O>) {
-        let price = unsafe { (*node_ptr).data.price() };
-        let level = self.level_mut(price);
-        level.remove_order(node_ptr);
+    pub fn remove_order(&mut self, handle: Handle) -> Option<O> {
+        let price = self.order(handle)?.price();
+        let (level, nodes) = self.level_mut(price);
+        let removed = level.remove_order(nodes, handle);
         let empty = level.is_empty();
         self.cleanup_level(price, empty);
+        removed
     }
 
     /// Bids: highest price first. Asks: lowest price first.
     #[inline]
     pub fn iter(&self) -> LevelIter<'_, O> {
+        let mut prices_iter = self.prices.iter();
+        let sparse = if self.is_bid {
+            prices_iter.next_back()
+        } else {
+            prices_iter.next()
+        };
         LevelIter {
             is_bid: self.is_bid,
-            prices_iter: self.prices.iter(),
+            prices_iter,
             levels: &self.levels,
+            sparse: sparse.map(|p| &self.levels[p]),
+            ladder: self.ladder.as_ref(),
+            dense: self.ladder.as_ref().and_then(|l| l.best(self.is_bid)),
+            nodes: &self.nodes,
         }
     }
 
-    /// Bids: highest price first. Asks: lowest price first.
+    /// Every order in priority order: best level first, FIFO within each level.
     #[inline]
-    pub fn iter_mut(&mut self) -> LevelIterMut<'_, O> {
-        LevelIterMut {
-            is_bid: self.is_bid,
-            prices_iter: self.prices.iter(),
-            levels: &mut self.levels,
+    pub fn orders(&self) -> OrderIter<'_, O> {
+        OrderIter {
+            levels: self.iter(),
+            current: None,
         }
     }
 }
@@ -140,44 +388,72 @@ pub struct LevelIter<'a, O: OrderInterface> {
     is_bid: bool,
     prices_iter: btree_set::Iter<'a, O::N>,
     levels: &'a FxHashMap<O::N, Level<O>>,
+    /// Next level from the tree.
+    sparse: Option<&'a Level<O>>,
+    ladder: Option<&'a Ladder<O>>,
+    /// Index of the next level on the ladder.
+    dense: Option<usize>,
+    nodes: &'a Slab<O>,
 }
 
 impl<'a, O: OrderInterface> Iterator for LevelIter<'a, O> {
-    type Item = &'a Level<O>;
+    type Item = LevelRef<'a, O>;
 
+    /// Merges the ladder and the tree, best price first.
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        let price = if self.is_bid {
-            self.prices_iter.next_back()
+        let dense = match (self.ladder, self.dense) {
+            (Some(ladder), Some(index)) => ladder.get(index),
+            _ => None,
+        };
+        let from_ladder = match (self.sparse, dense) {
+            (Some(sparse), Some(dense)) if self.is_bid => dense.price() > sparse.price(),
+            (Some(sparse), Some(dense)) => dense.price() < sparse.price(),
+            (_, dense) => dense.is_some(),
+        };
+        let level = if from_ladder {
+            let ladder = self.ladder.unwrap();
+            self.dense = ladder.after(self.dense.unwrap(), self.is_bid);
+            dense.unwrap()
         } else {
-            self.prices_iter.next()
+            let level = self.sparse?;
+            let price = if self.is_bid {
+                self.prices_iter.next_back()
+            } else {
+                self.prices_iter.next()
+            };
+            self.sparse = price.map(|p| &self.levels[p]);
+            level
         };
-        price.map(|p| self.levels.get(p).unwrap())
+        Some(LevelRef::new(level, self.nodes))
     }
 }
 
-pub struct LevelIterMut<'a, O: OrderInterface> {
-    is_bid: bool,
-    prices_iter: btree_set::Iter<'a, O::N>,
-    levels: &'a mut FxHashMap<O::N, Level<O>>,
+pub struct OrderIter<'a, O: OrderInterface> {
+    levels: LevelIter<'a, O>,
+    current: Option<(O::N, usize, list::Iter<'a, O>)>,
 }
 
-impl<'a, O: OrderInterface> Iterator for LevelIterMut<'a, O> {
-    type Item = &'a mut Level<O>;
+impl<'a, O: OrderInterface> Iterator for OrderIter<'a, O> {
+    type Item = DepthOrder<'a, O>;
 
-    #[inline(always)]
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let price = if self.is_bid {
-            self.prices_iter.next_back()
-        } else {
-            self.prices_iter.next()
-        };
-        price.map(|p| {
-            let level = self.levels.get_mut(p).unwrap();
-            // SAFETY: We iterate through each price exactly once, so we never
-            // create overlapping mutable references to the same level.
-            unsafe { &mut *(level as *mut Level<O>) }
-        })
+        loop {
+            if let Some((price, position, orders)) = &mut self.current
+                && let Some(order) = orders.next()
+            {
+                let item = DepthOrder {
+                    price: *price,
+                    position: *position,
+                    order,
+                };
+                *position += 1;
+                return Some(item);
+            }
+            let level = self.levels.next()?;
+            self.current = Some((level.price(), 0, level.iter()));
+        }
     }
 }
 
@@ -196,7 +472,7 @@ mod tests {
     #[test]
     fn test_insert_order() {
         let mut side = Side::<TestOrder>::new(true);
-        let _node_ptr = side.insert_order(TestOrder::new("1", true, 100, 50));
+        let _handle = side.insert_order(TestOrder::new("1", true, 100, 50));
         assert!(!side.is_empty());
         assert_eq!(side.height(), 1);
     }
@@ -221,21 +497,111 @@ mod tests {
     #[test]
     fn test_remove_order() {
         let mut side = Side::<TestOrder>::new(true);
-        let node_ptr = side.insert_order(TestOrder::new("1", true, 100, 50));
+        let handle = side.insert_order(TestOrder::new("1", true, 100, 50));
         side.insert_order(TestOrder::new("2", true, 100, 30));
-        side.remove_order(node_ptr);
+        side.remove_order(handle);
         assert_eq!(side.height(), 1);
     }
 
     #[test]
     fn test_remove_order_single_order() {
         let mut side = Side::<TestOrder>::new(true);
-        let node_ptr = side.insert_order(TestOrder::new("1", true, 100, 50));
-        side.remove_order(node_ptr);
+        let handle = side.insert_order(TestOrder::new("1", true, 100, 50));
+        side.remove_order(handle);
         let level_count: usize = side.iter().count();
         assert_eq!(level_count, 0);
     }
 
+    #[test]
+    fn test_depth() {
+        let mut side = Side::<TestOrder>::new(false);
+        side.insert_order(TestOrder::new("1", false, 100, 50));
+        side.insert_order(TestOrder::new("2", false, 100, 30));
+        side.insert_order(TestOrder::new("3", false, 110, 20));
+        side.insert_order(TestOrder::new("4", false, 120, 10));
+
+        let mut out = Vec::new();
+        side.depth(2, &mut out);
+        assert_eq!(out.len(), 2);
+        assert_eq!(
+            out[0],
+            DepthLevel {
+                price: 100,
+                quantity: 80,
+                orders: 2,
+                cumulative_quantity: 80,
+                cumulative_notional: 8000,
+            }
+        );
+        assert_eq!(
+            out[1],
+            DepthLevel {
+                price: 110,
+                quantity: 20,
+                orders: 1,
+                cumulative_quantity: 100,
+                cumulative_notional: 10200,
+            }
+        );
+
+        // Buffer is cleared, not appended to
+        side.depth(10, &mut out);
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[2].cumulative_quantity, 110);
+    }
+
+    #[test]
+    fn test_sweep() {
+        let mut side = Side::<TestOrder>::new(false);
+        side.insert_order(TestOrder::new("1", false, 100, 50));
+        side.insert_order(TestOrder::new("2", false, 101, 30));
+        side.insert_order(TestOrder::new("3", false, 103, 20));
+
+        let e = side.sweep(60, None);
+        assert_eq!(e.filled, 60);
+        assert_eq!(e.notional, 50 * 100 + 10 * 101);
+        assert_eq!(e.worst_price, Some(101));
+        assert_eq!(e.levels, 2);
+        assert_eq!(e.average_price(), Some(100));
+
+        // Runs out of liquidity
+        let e = side.sweep(500, None);
+        assert_eq!(e.filled, 100);
+        assert_eq!(e.levels, 3);
+        assert!(!e.is_complete(500));
+
+        // Limit stops the walk
+        let e = side.sweep(500, Some(101));
+        assert_eq!(e.filled, 80);
+        assert_eq!(e.worst_price, Some(101));
+
+        // Exactly consumes a level
+        let e = side.sweep(50, None);
+        assert_eq!(e.levels, 1);
+        assert!(e.is_complete(50));
+
+        let e = side.sweep(10, Some(99));
+        assert_eq!(e.filled, 0);
+        assert_eq!(e.worst_price, None);
+        assert_eq!(e.average_price(), None);
+
+        let mut bids = Side::<TestOrder>::new(true);
+        bids.insert_order(TestOrder::new("4", true, 100, 50));
+        bids.insert_order(TestOrder::new("5", true, 98, 50));
+        let e = bids.sweep(80, Some(99));
+        assert_eq!(e.filled, 50);
+        assert_eq!(e.levels, 1);
+    }
+
+    #[test]
+    fn test_level() {
+        let mut side = Side::<TestOrder>::new(true);
+        side.insert_order(TestOrder::new("1", true, 100, 50));
+        side.insert_order(TestOrder::new("2", true, 100, 30));
+        assert_eq!(side.level(100).unwrap().total_quantity(), 80);
+        assert!(side.level(200).is_none());
+    }
+
     #[test]
     fn test_iter_bids() {
         let mut side = Side::<TestOrder>::new(true);
@@ -257,14 +623,86 @@ mod tests {
     }
 
     #[test]
-    fn test_iter_mut() {
+    fn test_orders() {
         let mut side = Side::<TestOrder>::new(true);
         side.insert_order(TestOrder::new("1", true, 100, 50));
         side.insert_order(TestOrder::new("2", true, 200, 30));
-        for level in side.iter_mut() {
-            let _ = level.price();
+        side.insert_order(TestOrder::new("3", true, 100, 20));
+        side.insert_order(TestOrder::new("4", true, 200, 10));
+        let orders: Vec<(u64, usize, &str)> = side
+            .orders()
+            .map(|o| (o.price, o.position, o.order.id().as_str()))
+            .collect();
+        assert_eq!(
+            orders,
+            vec![(200, 0, "2"), (200, 1, "4"), (100, 0, "1"), (100, 1, "3")]
+        );
+        assert_eq!(Side::<TestOrder>::new(false).orders().count(), 0);
+    }
+
+    #[test]
+    fn test_stale_handle() {
+        let mut side = Side::<TestOrder>::new(true);
+        let h1 = side.insert_order(TestOrder::new("1", true, 100, 50));
+        let h2 = side.insert_order(TestOrder::new("2", true, 100, 30));
+        assert_eq!(side.queue_position(h2), Some((1, 50)));
+        assert_eq!(side.fill_order(h1, 50).unwrap().unwrap().id(), "1");
+
+        // The freed slot goes to "3", but h1 still does not reach it
+        let h3 = side.insert_order(TestOrder::new("3", true, 100, 20));
+        assert!(side.order(h1).is_none());
+        assert!(side.remove_order(h1).is_none());
+        assert!(side.fill_order(h1, 10).is_none());
+        assert_eq!(side.queue_position(h1), None);
+        assert_eq!(side.queue_position(h3), Some((1, 30)));
+        assert_eq!(side.level(100).unwrap().total_quantity(), 50);
+    }
+
+    #[test]
+    fn test_ladder_with_off_ladder_prices() {
+        for is_bid in [true, false] {
+            let mut side = Side::<TestOrder>::with_ladder(is_bid, 100, 200, 10);
+            let mut handles = Vec::new();
+            for (i, price) in [150, 95, 120, 205, 155, 120, 200, 100]
+                .into_iter()
+                .enumerate()
+            {
+                let order = TestOrder::new(&i.to_string(), is_bid, price, 10);
+                handles.push(side.insert_order(order));
+            }
+            assert_eq!(side.height(), 7);
+            let mut expected = vec![95, 100, 120, 150, 155, 200, 205];
+            if is_bid {
+                expected.reverse();
+            }
+            let prices: Vec<u64> = side.iter().map(|level| level.price()).collect();
+            assert_eq!(prices, expected);
+            assert_eq!(side.best(), Some((expected[0], 10)));
+            // Looking up the level after a price matches iterating
+            let after = |price| side.after(price).map(|level| level.price());
+            for pair in expected.windows(2) {
+                assert_eq!(after(pair[0]), Some(pair[1]));
+            }
+            assert_eq!(after(expected[6]), None);
+            assert_eq!(after(151), Some(if is_bid { 150 } else { 155 }));
+            assert_eq!(side.level(120).unwrap().total_quantity(), 20);
+            assert_eq!(side.queue_position(handles[5]), Some((1, 10)));
+
+            // Empty ladder levels and tree levels are both dropped
+            side.remove_order(handles[2]);
+            side.remove_order(handles[5]);
+            side.fill_order(handles[0], 10);
+            side.remove_order(if is_bid { handles[3] } else { handles[1] });
+            assert_eq!(side.height(), 4);
+            let prices: Vec<u64> = side.iter().map(|level| level.price()).collect();
+            let expected = if is_bid {
+                [200, 155, 100, 95]
+            } else {
+                [100, 155, 200, 205]
+            };
+            assert_eq!(prices, expected);
+            assert_eq!(side.best_price(), Some(expected[0]));
         }
-        assert_eq!(side.height(), 2);
     }
 
     #[test]
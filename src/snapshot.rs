@@ -0,0 +1,401 @@
+use crate::{checksum::Crc32, level::LevelRef, ob::OrderBook, order::OrderInterface};
+use std::fmt;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Snapshot format (little-endian)
+//
+//   magic "OBSN" | version u16 | flags u8 | sequence u64
+//   bids, then asks: level count u32, then per level (best first):
+//       order count u32, then per order (FIFO): length u32 | codec bytes
+//   crc32 u32 over everything before it
+//
+// Side, level and queue position are implied by where an order appears.
+// ─────────────────────────────────────────────────────────────────────────────
+
+const MAGIC: &[u8; 4] = b"OBSN";
+const VERSION: u16 = 1;
+const FLAG_HASH: u8 = 1;
+
+/// Encodes and decodes user orders inside a snapshot, since `O` is user-defined.
+/// Must round-trip id, side, price, quantity and remaining quantity.
+pub trait OrderCodec<O: OrderInterface> {
+    fn encode(&self, order: &O, out: &mut Vec<u8>);
+
+    /// Decodes an order from exactly the bytes `encode` produced, or None if invalid.
+    fn decode(&self, bytes: &[u8]) -> Option<O>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// Input ended before the snapshot did.
+    Truncated,
+    ChecksumMismatch,
+    /// The codec rejected an order's bytes.
+    InvalidOrder,
+    /// An order is on the wrong side or level, appears twice or has nothing
+    /// left, or a level is empty.
+    Inconsistent,
+    /// `restore_into` was given a book that already holds orders.
+    NotEmpty,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not an orderbook snapshot"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported snapshot version {v}"),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum mismatch"),
+            SnapshotError::InvalidOrder => write!(f, "order codec rejected an order"),
+            SnapshotError::Inconsistent => write!(f, "snapshot orders are inconsistent"),
+            SnapshotError::NotEmpty => write!(f, "cannot restore into a book with orders"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl<O: OrderInterface> OrderBook<O> {
+    /// Appends a versioned binary snapshot of every resting order, in priority
+    /// order, and the batch sequence to `out`. Take it between `apply` calls:
+    /// pending `eval` state is not captured. Trade tapes are not captured either.
+    pub fn snapshot<C: OrderCodec<O>>(&self, codec: &C, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        let flags = if self.book_hash().is_some() {
+            FLAG_HASH
+        } else {
+            0
+        };
+        out.push(flags);
+        out.extend_from_slice(&self.sequence().to_le_bytes());
+        write_levels(self.bid_depth(), self.bids(), codec, out);
+        write_levels(self.ask_depth(), self.asks(), codec, out);
+        let crc = Crc32::checksum(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Rebuilds a default book from `snapshot`. See `restore_into`.
+    #[inline]
+    pub fn restore<C: OrderCodec<O>>(snapshot: &[u8], codec: &C) -> Result<Self, SnapshotError> {
+        let mut ob = Self::default();
+        ob.restore_into(snapshot, codec)?;
+        Ok(ob)
+    }
+
+    /// Restores `snapshot` into this empty book, reproducing every level's FIFO
+    /// order and the sequence. The book keeps how it was created, such as its
+    /// `with_ladder` backend and node capacity. On error it is left empty.
+    pub fn restore_into<C: OrderCodec<O>>(
+        &mut self,
+        snapshot: &[u8],
+        codec: &C,
+    ) -> Result<(), SnapshotError> {
+        if !self.is_empty() {
+            return Err(SnapshotError::NotEmpty);
+        }
+        let result = self.read_snapshot(snapshot, codec);
+        if result.is_err() {
+            self.clear();
+        }
+        result
+    }
+
+    /// Restores orders from `snapshot` into this empty book, stopping at the first
+    /// error with whatever was restored so far still resting.
+    fn read_snapshot<C: OrderCodec<O>>(
+        &mut self,
+        snapshot: &[u8],
+        codec: &C,
+    ) -> Result<(), SnapshotError> {
+        let (body, crc) = snapshot
+            .split_last_chunk::<4>()
+            .ok_or(SnapshotError::Truncated)?;
+        let mut reader = Reader { bytes: body };
+        if reader.take(4)? != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        if Crc32::checksum(body) != u32::from_le_bytes(*crc) {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+        let flags = reader.array::<1>()?[0];
+        let sequence = u64::from_le_bytes(reader.array()?);
+
+        let zero = O::N::default();
+        for is_bid in [true, false] {
+            let mut last_price = None;
+            for _ in 0..reader.u32()? {
+                let mut price = None;
+                let count = reader.u32()?;
+                if count == 0 {
+                    return Err(SnapshotError::Inconsistent);
+                }
+                for _ in 0..count {
+                    let len = reader.u32()? as usize;
+                    let order = codec
+                        .decode(reader.take(len)?)
+                        .ok_or(SnapshotError::InvalidOrder)?;
+                    let misplaced = order.is_buy() != is_bid
+                        || order.remaining() == zero
+                        || match price {
+                            Some(p) => p != order.price(),
+                            None => last_price.is_some_and(|last| {
+                                if is_bid {
+                                    order.price() >= last
+                                } else {
+                                    order.price() <= last
+                                }
+                            }),
+                        };
+                    price = Some(order.price());
+                    if misplaced || !self.restore_order(order) {
+                        return Err(SnapshotError::Inconsistent);
+                    }
+                }
+                last_price = price.or(last_price);
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::Inconsistent);
+        }
+        if flags & FLAG_HASH != 0 {
+            self.track_hash();
+        }
+        self.set_sequence(sequence);
+        Ok(())
+    }
+}
+
+fn write_levels<'a, O: OrderInterface + 'a, C: OrderCodec<O>>(
+    count: usize,
+    levels: impl Iterator<Item = LevelRef<'a, O>>,
+    codec: &C,
+    out: &mut Vec<u8>,
+) {
+    out.extend_from_slice(&(count as u32).to_le_bytes());
+    for level in levels {
+        out.extend_from_slice(&(level.len() as u32).to_le_bytes());
+        for order in level.iter() {
+            let len_at = out.len();
+            out.extend_from_slice(&[0; 4]);
+            codec.encode(order, out);
+            let len = (out.len() - len_at - 4) as u32;
+            out[len_at..len_at + 4].copy_from_slice(&len.to_le_bytes());
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < n {
+            return Err(SnapshotError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    #[inline]
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Instruction, Op,
+        order::{TestCodec, TestOrder},
+    };
+
+    fn book() -> OrderBook<TestOrder> {
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.track_hash();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 1000, 100)),
+            Op::Insert(TestOrder::new("s2", false, 1000, 50)),
+            Op::Insert(TestOrder::new("s3", false, 1010, 20)),
+            Op::Insert(TestOrder::new("b1", true, 990, 30)),
+            Op::Insert(TestOrder::new("b2", true, 980, 10)),
+            Op::Insert(TestOrder::new("b3", true, 990, 40)),
+        ]);
+        ob.apply(instructions).unwrap();
+        ob.apply(vec![Instruction::Fill(String::from("s1"), 60)])
+            .unwrap();
+        ob.set_sequence(7);
+        ob
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let ob = book();
+        let mut bytes = Vec::new();
+        ob.snapshot(&TestCodec, &mut bytes);
+
+        let mut restored = OrderBook::restore(&bytes, &TestCodec).unwrap();
+        assert_eq!(restored.len(), ob.len());
+        assert_eq!(restored.l3_checksum(), ob.l3_checksum());
+        assert_eq!(restored.book_hash(), ob.book_hash());
+        assert_eq!(restored.sequence(), 7);
+        assert_eq!(restored.order(&String::from("s1")).unwrap().remaining(), 40);
+        assert_eq!(
+            restored.queue_position(&String::from("s2")),
+            ob.queue_position(&String::from("s2"))
+        );
+
+        // Priority is preserved: s1 is still first in line at 1000
+        let (m, _) = restored.eval_insert(TestOrder::new("b9", true, 1000, 40));
+        assert_eq!(m.unwrap().makers, vec![(String::from("s1"), 40)]);
+
+        let mut again = Vec::new();
+        restored.snapshot(&TestCodec, &mut again);
+        assert_eq!(again, bytes);
+    }
+
+    #[test]
+    fn test_restore_into() {
+        let mut bytes = Vec::new();
+        book().snapshot(&TestCodec, &mut bytes);
+
+        let mut ladder = OrderBook::with_ladder(900, 1100, 10);
+        ladder.restore_into(&bytes, &TestCodec).unwrap();
+        assert_eq!(ladder.l3_checksum(), book().l3_checksum());
+        assert_eq!(ladder.sequence(), 7);
+
+        let mut capacity = OrderBook::<TestOrder>::with_capacity(64);
+        capacity.restore_into(&bytes, &TestCodec).unwrap();
+        assert!(capacity.side(true).capacity() >= 64);
+
+        let mut full = book();
+        assert_eq!(
+            full.restore_into(&bytes, &TestCodec),
+            Err(SnapshotError::NotEmpty)
+        );
+        assert_eq!(full.l3_checksum(), book().l3_checksum());
+
+        // A snapshot that fails partway, here cut short in its last order with a
+        // valid checksum, leaves the caller's book empty but usable
+        let mut cut = bytes[..bytes.len() - 4 - 3].to_vec();
+        let crc = Crc32::checksum(&cut);
+        cut.extend_from_slice(&crc.to_le_bytes());
+        let mut ob = OrderBook::<TestOrder>::with_ladder(900, 1100, 10);
+        ob.track_hash();
+        assert_eq!(
+            ob.restore_into(&cut, &TestCodec),
+            Err(SnapshotError::Truncated)
+        );
+        assert!(ob.is_empty());
+        assert_eq!(ob.book_hash(), Some(0));
+        assert_eq!(ob.sequence(), 0);
+        ob.restore_into(&bytes, &TestCodec).unwrap();
+        assert_eq!(ob.l3_checksum(), book().l3_checksum());
+    }
+
+    #[test]
+    fn test_empty_roundtrip() {
+        let ob = OrderBook::<TestOrder>::default();
+        let mut bytes = Vec::new();
+        ob.snapshot(&TestCodec, &mut bytes);
+        let restored = OrderBook::restore(&bytes, &TestCodec).unwrap();
+        assert!(restored.is_empty());
+        assert!(restored.book_hash().is_none());
+    }
+
+    #[test]
+    fn test_corruption() {
+        let mut bytes = Vec::new();
+        book().snapshot(&TestCodec, &mut bytes);
+        let restore = |b: &[u8]| OrderBook::restore(b, &TestCodec).err();
+
+        assert_eq!(restore(&bytes[..2]), Some(SnapshotError::Truncated));
+        assert_eq!(
+            restore(&bytes[..bytes.len() - 5]),
+            Some(SnapshotError::ChecksumMismatch)
+        );
+
+        let mut flipped = bytes.clone();
+        flipped[20] ^= 0xff;
+        assert_eq!(restore(&flipped), Some(SnapshotError::ChecksumMismatch));
+
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert_eq!(restore(&magic), Some(SnapshotError::BadMagic));
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert_eq!(
+            restore(&version),
+            Some(SnapshotError::UnsupportedVersion(9))
+        );
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        // An ask encoded on the bid side
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.restore_order(TestOrder::new("s1", false, 1000, 10));
+        let mut bytes = Vec::new();
+        ob.snapshot(&TestCodec, &mut bytes);
+        let asks_at = 4 + 2 + 1 + 8;
+        let mut swapped = bytes[..asks_at].to_vec();
+        swapped.extend_from_slice(&bytes[asks_at + 4..bytes.len() - 4]);
+        swapped.extend_from_slice(&0u32.to_le_bytes());
+        let crc = Crc32::checksum(&swapped);
+        swapped.extend_from_slice(&crc.to_le_bytes());
+        assert_eq!(
+            OrderBook::restore(&swapped, &TestCodec).err(),
+            Some(SnapshotError::Inconsistent)
+        );
+    }
+
+    #[test]
+    fn test_empty_level_and_order() {
+        let with_crc = |mut bytes: Vec<u8>| {
+            let crc = Crc32::checksum(&bytes);
+            bytes.extend_from_slice(&crc.to_le_bytes());
+            bytes
+        };
+        let mut bytes = Vec::new();
+        OrderBook::<TestOrder>::default().snapshot(&TestCodec, &mut bytes);
+        let header = &bytes[..4 + 2 + 1 + 8];
+
+        // One bid level holding no orders
+        let mut empty_level = header.to_vec();
+        for count in [1u32, 0, 0] {
+            empty_level.extend_from_slice(&count.to_le_bytes());
+        }
+        assert_eq!(
+            OrderBook::restore(&with_crc(empty_level), &TestCodec).err(),
+            Some(SnapshotError::Inconsistent)
+        );
+
+        let mut filled = TestOrder::new("b1", true, 100, 10);
+        filled.fill(10);
+        let mut ob = OrderBook::<TestOrder>::default();
+        ob.restore_order(filled);
+        let mut bytes = Vec::new();
+        ob.snapshot(&TestCodec, &mut bytes);
+        assert_eq!(
+            OrderBook::restore(&bytes, &TestCodec).err(),
+            Some(SnapshotError::Inconsistent)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::order::OrderInterface;
+use std::collections::VecDeque;
+
+/// A trade against a resting order, recorded when a `Fill` is applied.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Trade<O: OrderInterface> {
+    /// The resting order that was filled.
+    pub maker: O::T,
+    /// Side of the incoming (aggressor) order.
+    pub is_buy: bool,
+    pub price: O::N,
+    pub quantity: O::N,
+}
+
+impl<O: OrderInterface> Clone for Trade<O> {
+    fn clone(&self) -> Self {
+        Self {
+            maker: self.maker.clone(),
+            is_buy: self.is_buy,
+            price: self.price,
+            quantity: self.quantity,
+        }
+    }
+}
+
+/// Running statistics over every trade since the session started.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TradeStats<O: OrderInterface> {
+    pub last_price: Option<O::N>,
+    pub last_quantity: Option<O::N>,
+    pub high: Option<O::N>,
+    pub low: Option<O::N>,
+    /// Total traded quantity.
+    pub volume: O::N,
+    /// Total traded price * quantity.
+    pub notional: O::N,
+    pub count: u64,
+}
+
+impl<O: OrderInterface> Clone for TradeStats<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O: OrderInterface> Copy for TradeStats<O> {}
+
+impl<O: OrderInterface> Default for TradeStats<O> {
+    fn default() -> Self {
+        Self {
+            last_price: None,
+            last_quantity: None,
+            high: None,
+            low: None,
+            volume: O::N::default(),
+            notional: O::N::default(),
+            count: 0,
+        }
+    }
+}
+
+impl<O: OrderInterface> TradeStats<O> {
+    /// Volume-weighted average price, truncated towards zero for integer `O::N`.
+    #[inline]
+    pub fn vwap(&self) -> Option<O::N> {
+        (self.volume > O::N::default()).then(|| self.notional / self.volume)
+    }
+
+    #[inline]
+    fn record(&mut self, price: O::N, quantity: O::N) {
+        self.last_price = Some(price);
+        self.last_quantity = Some(quantity);
+        self.high = Some(self.high.map_or(price, |h| h.max(price)));
+        self.low = Some(self.low.map_or(price, |l| l.min(price)));
+        self.volume += quantity;
+        self.notional += price * quantity;
+        self.count += 1;
+    }
+}
+
+/// The most recent trades (up to a fixed capacity) and session statistics.
+pub struct Tape<O: OrderInterface> {
+    capacity: usize,
+    trades: VecDeque<Trade<O>>,
+    stats: TradeStats<O>,
+}
+
+impl<O: OrderInterface> Clone for Tape<O> {
+    fn clone(&self) -> Self {
+        Self {
+            capacity: self.capacity,
+            trades: self.trades.clone(),
+            stats: self.stats,
+        }
+    }
+}
+
+impl<O: OrderInterface> Tape<O> {
+    /// Creates a tape keeping the last `capacity` trades. Zero keeps statistics only.
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            trades: VecDeque::with_capacity(capacity),
+            stats: TradeStats::default(),
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the retained trades, oldest first.
+    #[inline]
+    pub fn trades(&self) -> impl DoubleEndedIterator<Item = &Trade<O>> {
+        self.trades.iter()
+    }
+
+    /// Returns the most recent trade, if retained.
+    #[inline]
+    pub fn last(&self) -> Option<&Trade<O>> {
+        self.trades.back()
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &TradeStats<O> {
+        &self.stats
+    }
+
+    /// Clears retained trades and statistics, e.g. at the start of a session.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.trades.clear();
+        self.stats = TradeStats::default();
+    }
+
+    /// Records `quantity` traded against resting `maker`.
+    #[inline]
+    pub(crate) fn record(&mut self, maker: &O, quantity: O::N) {
+        let price = maker.price();
+        self.stats.record(price, quantity);
+        if self.capacity == 0 {
+            return;
+        }
+        if self.trades.len() == self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(Trade {
+            maker: maker.id().clone(),
+            is_buy: !maker.is_buy(),
+            price,
+            quantity,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::TestOrder;
+
+    #[test]
+    fn test_record() {
+        let mut tape = Tape::<TestOrder>::new(2);
+        assert_eq!(tape.stats().vwap(), None);
+        assert!(tape.last().is_none());
+
+        tape.record(&TestOrder::new("s1", false, 100, 10), 10);
+        tape.record(&TestOrder::new("s2", false, 102, 30), 30);
+        tape.record(&TestOrder::new("b1", true, 99, 20), 10);
+
+        let stats = tape.stats();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.volume, 50);
+        assert_eq!(stats.notional, 1000 + 3060 + 990);
+        assert_eq!(stats.vwap(), Some(101));
+        assert_eq!(stats.high, Some(102));
+        assert_eq!(stats.low, Some(99));
+        assert_eq!(stats.last_price, Some(99));
+        assert_eq!(stats.last_quantity, Some(10));
+
+        // Oldest trade evicted
+        let makers: Vec<&str> = tape.trades().map(|t| t.maker.as_str()).collect();
+        assert_eq!(makers, vec!["s2", "b1"]);
+        assert!(!tape.last().unwrap().is_buy);
+
+        tape.reset();
+        assert_eq!(tape.stats().count, 0);
+        assert_eq!(tape.trades().count(), 0);
+    }
+
+    #[test]
+    fn test_stats_only() {
+        let mut tape = Tape::<TestOrder>::new(0);
+        tape.record(&TestOrder::new("s1", false, 100, 10), 10);
+        assert_eq!(tape.trades().count(), 0);
+        assert_eq!(tape.stats().volume, 10);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    hash::FxHashMap,
+    ob::{ApplyError, Instruction, OrderBook, overfills},
+    order::OrderInterface,
+};
+
+/// An order as (is_buy, price, remaining).
+type OrderState<O> = (bool, <O as OrderInterface>::N, <O as OrderInterface>::N);
+
+/// Checks a batch of instructions against the book as it would be after each
+/// preceding instruction, without mutating the book.
+pub(crate) struct Validator<'a, O: OrderInterface> {
+    ob: &'a OrderBook<O>,
+    /// Orders touched by the batch so far, None once gone.
+    orders: FxHashMap<&'a O::T, Option<OrderState<O>>>,
+}
+
+impl<'a, O: OrderInterface> Validator<'a, O> {
+    #[inline]
+    pub(crate) fn new(ob: &'a OrderBook<O>) -> Self {
+        Self {
+            ob,
+            orders: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the first instruction `apply` would reject, if any.
+    pub(crate) fn check(
+        &mut self,
+        instructions: &'a [Instruction<O>],
+    ) -> Result<(), ApplyError<O>> {
+        let zero = O::N::default();
+        for (index, instruction) in instructions.iter().enumerate() {
+            match instruction {
+                Instruction::Insert(order, remaining) => {
+                    let (id, is_buy, price) = (order.id(), order.is_buy(), order.price());
+                    if self.resting(id).is_some() {
+                        let id = id.clone();
+                        return Err(ApplyError::DuplicateOrder { index, id });
+                    }
+                    if *remaining == zero {
+                        let id = id.clone();
+                        return Err(ApplyError::ZeroQuantity { index, id });
+                    }
+                    if overfills(order, *remaining) {
+                        return Err(ApplyError::Overfill {
+                            index,
+                            id: id.clone(),
+                            quantity: *remaining,
+                            remaining: order.remaining(),
+                        });
+                    }
+                    self.orders.insert(id, Some((is_buy, price, *remaining)));
+                }
+                Instruction::Delete(id) => {
+                    if self.resting(id).is_none() {
+                        let id = id.clone();
+                        return Err(ApplyError::UnknownOrder { index, id });
+                    }
+                    self.orders.insert(id, None);
+                }
+                Instruction::Fill(id, quantity) | Instruction::Reduce(id, quantity) => {
+                    let Some((is_buy, price, remaining)) = self.resting(id) else {
+                        let id = id.clone();
+                        return Err(ApplyError::UnknownOrder { index, id });
+                    };
+                    if *quantity == zero {
+                        let id = id.clone();
+                        return Err(ApplyError::ZeroQuantity { index, id });
+                    }
+                    if *quantity > remaining {
+                        return Err(ApplyError::Overfill {
+                            index,
+                            id: id.clone(),
+                            quantity: *quantity,
+                            remaining,
+                        });
+                    }
+                    let left = remaining - *quantity;
+                    self.orders
+                        .insert(id, (left > zero).then_some((is_buy, price, left)));
+                }
+                Instruction::NoOp(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn resting(&self, id: &O::T) -> Option<OrderState<O>> {
+        match self.orders.get(id) {
+            Some(state) => *state,
+            None => self
+                .ob
+                .order(id)
+                .map(|order| (order.is_buy(), order.price(), order.remaining())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ApplyError, Instruction, Op, OrderBook,
+        order::{OrderInterface, TestOrder},
+    };
+
+    fn book() -> OrderBook<TestOrder> {
+        let mut ob = OrderBook::default();
+        let (_, instructions) = ob.eval(vec![
+            Op::Insert(TestOrder::new("s1", false, 100, 10)),
+            Op::Insert(TestOrder::new("s2", false, 101, 10)),
+            Op::Insert(TestOrder::new("b1", true, 99, 10)),
+        ]);
+        ob.apply(instructions).unwrap();
+        ob
+    }
+
+    #[test]
+    fn test_atomic_rejects_whole_batch() {
+        let mut ob = book();
+        let hash = ob.compute_hash();
+        let err = ob.apply_atomic(vec![
+            Instruction::Fill(String::from("s1"), 5),
+            Instruction::Delete(String::from("b1")),
+            Instruction::Fill(String::from("s1"), 6),
+        ]);
+        assert_eq!(
+            err,
+            Err(ApplyError::Overfill {
+                index: 2,
+                id: String::from("s1"),
+                quantity: 6,
+                remaining: 5
+            })
+        );
+        assert_eq!(ob.compute_hash(), hash);
+        assert_eq!(ob.len(), 3);
+    }
+
+    #[test]
+    fn test_atomic_tracks_batch_state() {
+        let mut ob = book();
+
+        // s1 is gone once filled away earlier in the batch
+        let err = ob.apply_atomic(vec![
+            Instruction::Fill(String::from("s1"), 10),
+            Instruction::Reduce(String::from("s1"), 1),
+        ]);
+        assert_eq!(err.unwrap_err().index(), 1);
+        assert_eq!(ob.best_ask(), Some((100, 10)));
+
+        // An order inserted earlier in the batch can be filled, even behind s1
+        ob.apply_atomic(vec![
+            Instruction::Insert(TestOrder::new("s3", false, 102, 5), 5),
+            Instruction::Fill(String::from("s3"), 2),
+            Instruction::Reduce(String::from("s3"), 1),
+        ])
+        .unwrap();
+        assert_eq!(ob.order(&String::from("s3")).unwrap().remaining(), 2);
+
+        // eval rests the taker before its fills, which must still validate
+        let (_, instructions) = ob.eval(vec![Op::Insert(TestOrder::new("b2", true, 101, 23))]);
+        ob.apply_atomic(instructions).unwrap();
+        assert_eq!(ob.best_bid(), Some((101, 3)));
+        assert_eq!(ob.best_ask(), Some((102, 2)));
+    }
+
+    #[test]
+    fn test_atomic_unknown_and_duplicate() {
+        let mut ob = book();
+        let err = ob.apply_atomic(vec![
+            Instruction::Delete(String::from("s1")),
+            Instruction::Fill(String::from("s1"), 1),
+        ]);
+        assert_eq!(err.unwrap_err().index(), 1);
+
+        let err = ob.apply_atomic(vec![
+            Instruction::Insert(TestOrder::new("b9", true, 90, 5), 5),
+            Instruction::Insert(TestOrder::new("b9", true, 91, 5), 5),
+        ]);
+        assert_eq!(
+            err,
+            Err(ApplyError::DuplicateOrder {
+                index: 1,
+                id: String::from("b9")
+            })
+        );
+        assert!(ob.order(&String::from("b9")).is_none());
+    }
+
+    #[test]
+    fn test_atomic_zero_quantity() {
+        let mut ob = book();
+        let hash = ob.compute_hash();
+        for instruction in [
+            Instruction::Insert(TestOrder::new("b9", true, 90, 5), 0),
+            Instruction::Fill(String::from("s1"), 0),
+            Instruction::Reduce(String::from("b1"), 0),
+        ] {
+            let err = ob.apply_atomic(vec![Instruction::Fill(String::from("s2"), 1), instruction]);
+            assert!(matches!(
+                err,
+                Err(ApplyError::ZeroQuantity { index: 1, .. })
+            ));
+        }
+        assert_eq!(ob.compute_hash(), hash);
+    }
+}